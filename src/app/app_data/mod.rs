@@ -24,6 +24,7 @@ pub struct AppData {
     pub facebook_provider: Arc<Mutex<FacebookProvider>>,
     pub google_provider: Arc<Mutex<GoogleProvider>>,
     pub cyber_sherlock_auth_provider: Arc<Mutex<CyberSherlockAuthProvider>>,
+    pub notification_provider: Arc<Mutex<NotificationProvider>>,
     pub user_service: Arc<Mutex<UserService>>,
 }
 
@@ -86,11 +87,16 @@ impl AppData {
             storage_service,
             user_cache_service,
             session_cache_service,
+            NotificationProvider::new(Box::new(async_http_request)),
         )
         .await?;
 
+        // notification provider to send user notifications out of auth flow
+        let user_notification_provider = NotificationProvider::new(Box::new(async_http_request));
+
         let app_data = AppData {
             cyber_sherlock_auth_provider: Arc::new(Mutex::new(cyber_sherlock_auth_provider)),
+            notification_provider: Arc::new(Mutex::new(user_notification_provider)),
             facebook_provider: Arc::new(Mutex::new(facebook_provider)),
             google_provider: Arc::new(Mutex::new(google_provider)),
             user_service: Arc::new(Mutex::new(user_service)),
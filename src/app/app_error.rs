@@ -1,11 +1,16 @@
 use std::sync::PoisonError;
 
-use actix_web::{error, http::header::ContentType, Error, HttpRequest, HttpResponse};
+use actix_web::{
+    error,
+    http::{header::ContentType, StatusCode},
+    Error, HttpRequest, HttpResponse,
+};
 use log::error;
 use thiserror::Error;
 use validator::ValidationError;
 
 use super::{
+    middlewares::auth_guard::AuthError,
     providers::error::ProviderError,
     services::{
         cache::error::CacheServiceError, common::error_as_json,
@@ -32,6 +37,12 @@ pub enum AppError {
 
     #[error("{0}")]
     ValidationError(#[from] ValidationError),
+
+    #[error("{0}")]
+    AuthError(#[from] AuthError),
+
+    #[error("Too many anonymous sessions from the client, try later")]
+    AnonymousSessionLimitError,
 }
 
 impl<T> From<PoisonError<T>> for AppError {
@@ -42,10 +53,33 @@ impl<T> From<PoisonError<T>> for AppError {
 }
 
 impl error::ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::UserServiceError(UserServiceError::DuplicateUserError) => {
+                StatusCode::CONFLICT
+            }
+            AppError::UserServiceError(UserServiceError::SessionLimitExceededError) => {
+                StatusCode::FORBIDDEN
+            }
+            AppError::UserServiceError(UserServiceError::UserStatusError(_)) => {
+                StatusCode::FORBIDDEN
+            }
+            AppError::AuthError(AuthError::PermissionDenied(_)) => StatusCode::FORBIDDEN,
+            AppError::AuthError(_) => StatusCode::UNAUTHORIZED,
+            AppError::AnonymousSessionLimitError => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
     fn error_response(&self) -> HttpResponse {
+        let json_error = match self {
+            AppError::UserServiceError(UserServiceError::UserStatusError(err)) => err.to_json(),
+            AppError::AuthError(err) => error_as_json(err.get_message()),
+            _ => error_as_json(self.to_string().as_ref()),
+        };
         HttpResponse::build(self.status_code())
             .insert_header(ContentType::json())
-            .json(error_as_json(self.to_string().as_ref()))
+            .json(json_error)
     }
 }
 
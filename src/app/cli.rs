@@ -0,0 +1,117 @@
+use std::str::FromStr;
+
+use bson::oid::ObjectId;
+
+use crate::config::user_config::UserConfig;
+
+use super::{
+    app_error::AppError,
+    models::role::Role,
+    providers::notification::provider::NotificationProvider,
+    services::{
+        cache::{common::CacheServiceType, service::RedisCacheService},
+        common::async_http_request,
+        storage::service::StorageService,
+        user::service::UserService,
+    },
+};
+
+const MERGE_USERS_COMMAND: &str = "merge-users";
+const NORMALIZE_USERS_COMMAND: &str = "normalize-users";
+const SET_ROLES_COMMAND: &str = "set-roles";
+const DRY_RUN_FLAG: &str = "--dry-run";
+
+fn print_usage() {
+    println!(
+        "Usage:\n  cs_auth_service {} <target_user_id> <source_user_id> [{}]\n  cs_auth_service {}\n  cs_auth_service {} <user_id> [<role>,...]",
+        MERGE_USERS_COMMAND, DRY_RUN_FLAG, NORMALIZE_USERS_COMMAND, SET_ROLES_COMMAND
+    );
+}
+
+/// runs admin command passed in command line arguments
+pub async fn run_command(args: Vec<String>) -> Result<(), AppError> {
+    match args.first().map(|command| command.as_str()) {
+        Some(MERGE_USERS_COMMAND) => merge_users(&args[1..]).await,
+        Some(NORMALIZE_USERS_COMMAND) => normalize_users().await,
+        Some(SET_ROLES_COMMAND) => set_roles(&args[1..]).await,
+        _ => {
+            print_usage();
+            Ok(())
+        }
+    }
+}
+
+async fn merge_users(args: &[String]) -> Result<(), AppError> {
+    let user_ids: Vec<ObjectId> = args
+        .iter()
+        .filter(|arg| arg.as_str() != DRY_RUN_FLAG)
+        .filter_map(|arg| ObjectId::parse_str(arg).ok())
+        .collect();
+    if user_ids.len() != 2 {
+        print_usage();
+        return Ok(());
+    }
+    let dry_run = args.iter().any(|arg| arg.as_str() == DRY_RUN_FLAG);
+
+    let mut user_service = get_user_service().await?;
+    let report = user_service
+        .merge_users(&user_ids[0], &user_ids[1], dry_run)
+        .await?;
+    println!("{:#?}", report);
+    Ok(())
+}
+
+async fn normalize_users() -> Result<(), AppError> {
+    let mut user_service = get_user_service().await?;
+    let report = user_service.normalize_users().await?;
+    println!("{:#?}", report);
+    Ok(())
+}
+
+/// sets the user roles, it's the way to bootstrap the first admin
+/// who assigns roles to other users by the admin API
+async fn set_roles(args: &[String]) -> Result<(), AppError> {
+    let user_id = match args.first().and_then(|arg| ObjectId::parse_str(arg).ok()) {
+        Some(user_id) => user_id,
+        None => {
+            print_usage();
+            return Ok(());
+        }
+    };
+    // no roles argument removes all user roles
+    let roles: Result<Vec<Role>, &str> = args
+        .get(1)
+        .map(|roles| {
+            roles
+                .split(',')
+                .filter(|role| !role.trim().is_empty())
+                .map(Role::from_str)
+                .collect()
+        })
+        .unwrap_or_else(|| Ok(vec![]));
+    let roles = match roles {
+        Ok(roles) => roles,
+        Err(err) => {
+            println!("{}", err);
+            print_usage();
+            return Ok(());
+        }
+    };
+
+    let mut user_service = get_user_service().await?;
+    let user = user_service.set_user_roles(&user_id, roles, None).await?;
+    println!("User {} roles: {:?}", user.id, user.roles);
+    Ok(())
+}
+
+async fn get_user_service() -> Result<UserService, AppError> {
+    let user_service = UserService::new(
+        UserConfig::new(),
+        StorageService::new().await?,
+        RedisCacheService::new(CacheServiceType::User)?,
+        RedisCacheService::new(CacheServiceType::Session)?,
+        NotificationProvider::new(Box::new(async_http_request)),
+    )
+    .await?;
+    Ok(user_service)
+}
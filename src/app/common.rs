@@ -9,7 +9,31 @@ pub mod api_path {
     pub const CALLBACK: &str = "/callback";
     pub const LOGOUT: &str = "/logout";
     pub const STATUS: &str = "/status";
+    pub const METRICS: &str = "/metrics";
     pub const REGISTER: &str = "/register";
+    pub const PASSWORD_RESET: &str = "/password-reset";
     pub const USER: &str = "/user";
     pub const ME: &str = "/me";
+    pub const ME_EXPORT: &str = "/me/export";
+    pub const ME_EMAIL: &str = "/me/email";
+    pub const ME_PHONE: &str = "/me/phone";
+    pub const ME_CONTACT_CONFIRM: &str = "/me/contact/confirm";
+    pub const USER_EXPORT: &str = "/export/{export_token}";
+    pub const USER_SESSIONS: &str = "/sessions";
+    // has to be routed before USER_SESSION to not match `others` as session id
+    pub const USER_OTHER_SESSIONS: &str = "/sessions/others";
+    pub const USER_SESSION: &str = "/sessions/{session_id}";
+    pub const USER_PROVIDER: &str = "/providers/{provider}";
+    pub const USER_ACTIVE_PROFILE: &str = "/active-profile/{provider}";
+    pub const USER_MERGE_CONFIRM: &str = "/merge/confirm";
+    pub const ADMIN: &str = "/admin";
+    pub const USERS: &str = "/users";
+    // has to be routed before ADMIN_USER to not match `merge` as user id
+    pub const USERS_MERGE: &str = "/users/merge";
+    pub const ADMIN_USER: &str = "/users/{user_id}";
+    pub const ADMIN_USER_SUSPENSION: &str = "/users/{user_id}/suspension";
+    pub const ADMIN_USER_SESSIONS: &str = "/users/{user_id}/sessions";
+    pub const ADMIN_USER_PASSWORD_RESET: &str = "/users/{user_id}/password-reset";
+    pub const USER_ROLES: &str = "/users/{user_id}/roles";
+    pub const USER_MAX_SESSIONS: &str = "/users/{user_id}/max-sessions";
 }
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::app::{models::role::Role, repositories::user::repository::UserSearch};
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct MergeUsersQueryData {
+    // user which stays after merge
+    #[validate(length(equal = 24))]
+    pub target_user_id: String,
+    // user which is merged into target user and deleted
+    #[validate(length(equal = 24))]
+    pub source_user_id: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct UserRolesQueryData {
+    // replaces all user roles, empty list makes the user regular one
+    pub roles: Vec<Role>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct UserMaxSessionsQueryData {
+    // overrides the role limits, 0 means no limit, null removes the override
+    #[validate(range(max = 1000))]
+    pub max_sessions: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct SuspendUserQueryData {
+    // shown to the user on login and requests
+    #[validate(length(min = 1, max = 512))]
+    pub reason: String,
+    // no until date means till the user is reactivated
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct SearchUsersQueryData {
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    // system user id or provider(Google, Facebook) user id
+    pub provider_id: Option<String>,
+    #[validate(length(min = 2, max = 64))]
+    pub name: Option<String>,
+    // the max page keeps the skipped users count in the storage range
+    #[serde(default = "default_page")]
+    #[validate(range(min = 1, max = 10000))]
+    pub page: u64,
+    #[serde(default = "default_per_page")]
+    #[validate(range(min = 1, max = 100))]
+    pub per_page: u64,
+}
+
+impl SearchUsersQueryData {
+    pub fn to_user_search(&self) -> UserSearch {
+        UserSearch {
+            email: self.email.clone(),
+            phone: self.phone.clone(),
+            provider_id: self.provider_id.clone(),
+            name: self.name.clone(),
+        }
+    }
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_per_page() -> u64 {
+    20
+}
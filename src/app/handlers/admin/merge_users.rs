@@ -0,0 +1,40 @@
+use actix_web::{web, HttpResponse};
+use actix_web_validator::Json;
+use bson::oid::ObjectId;
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{BAD_USER_ID, MERGE_USER_INTO_ITSELF, NO_USER_FOUND},
+    services::{common::error_as_json, user::error::UserServiceError},
+};
+
+use super::common::MergeUsersQueryData;
+
+/// merges source user into target user, returns report with applied(or planned on dry run) changes
+pub async fn merge_users(
+    app_data: web::Data<AppData>,
+    query_data: Json<MergeUsersQueryData>,
+) -> Result<HttpResponse, AppError> {
+    let (target_user_id, source_user_id) = match (
+        ObjectId::parse_str(&query_data.target_user_id),
+        ObjectId::parse_str(&query_data.source_user_id),
+    ) {
+        (Ok(target_user_id), Ok(source_user_id)) => (target_user_id, source_user_id),
+        _ => return Ok(HttpResponse::BadRequest().json(error_as_json(BAD_USER_ID))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    match user_service
+        .merge_users(&target_user_id, &source_user_id, query_data.dry_run)
+        .await
+    {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(UserServiceError::UserNotFoundError) => {
+            Ok(HttpResponse::NotFound().json(error_as_json(NO_USER_FOUND)))
+        }
+        Err(UserServiceError::BadMergeRequestError) => {
+            Ok(HttpResponse::BadRequest().json(error_as_json(MERGE_USER_INTO_ITSELF)))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod common;
+pub mod merge_users;
+pub mod user_roles;
+pub mod users;
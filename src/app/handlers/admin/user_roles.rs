@@ -0,0 +1,77 @@
+use actix_web::{web, HttpResponse};
+use actix_web_validator::Json;
+use bson::oid::ObjectId;
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{BAD_USER_ID, NO_USER_FOUND},
+    models::authenticated_session::AuthenticatedSession,
+    services::{common::error_as_json, user::error::UserServiceError},
+};
+
+use super::common::{UserMaxSessionsQueryData, UserRolesQueryData};
+
+/// sets the user roles, it takes effect on the next request of the user
+pub async fn set_user_roles(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    query_data: Json<UserRolesQueryData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let user_id = match ObjectId::parse_str(path.into_inner()) {
+        Ok(user_id) => user_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().json(error_as_json(BAD_USER_ID))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    match user_service
+        .set_user_roles(&user_id, query_data.roles.clone(), Some(&session.user_id))
+        .await
+    {
+        Ok(user) => {
+            log::info!(
+                "User {} roles set to {:?} by {}",
+                &user.id,
+                &user.roles,
+                &session.user_id
+            );
+            Ok(HttpResponse::Ok().json(user.to_json()))
+        }
+        Err(UserServiceError::UserNotFoundError) => {
+            Ok(HttpResponse::NotFound().json(error_as_json(NO_USER_FOUND)))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// sets or removes the user own max sessions limit, it takes effect on the next login of the user
+pub async fn set_user_max_sessions(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    query_data: Json<UserMaxSessionsQueryData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let user_id = match ObjectId::parse_str(path.into_inner()) {
+        Ok(user_id) => user_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().json(error_as_json(BAD_USER_ID))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    match user_service
+        .set_user_max_sessions(&user_id, query_data.max_sessions, &session.user_id)
+        .await
+    {
+        Ok(user) => {
+            log::info!(
+                "User {} max sessions set to {:?} by {}",
+                &user.id,
+                &user.max_sessions,
+                &session.user_id
+            );
+            Ok(HttpResponse::Ok().json(user.to_json()))
+        }
+        Err(UserServiceError::UserNotFoundError) => {
+            Ok(HttpResponse::NotFound().json(error_as_json(NO_USER_FOUND)))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
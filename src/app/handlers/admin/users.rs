@@ -0,0 +1,176 @@
+use actix_web::{web, HttpResponse};
+use actix_web_validator::{Json, Query};
+use bson::oid::ObjectId;
+use chrono::Utc;
+use serde_json::json;
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{
+        BAD_USER_ID, NO_CYBER_SHERLOCK_PROFILE, NO_USER_FOUND, NO_VERIFIED_CONTACT,
+        PASSWORD_RESET_SENT, SUCCESS, SUSPENSION_END_IN_PAST,
+    },
+    models::{
+        authenticated_session::AuthenticatedSession, session::SessionInfo, user_status::Suspension,
+    },
+    services::{
+        common::{error_as_json, result_as_json},
+        user::error::UserServiceError,
+    },
+};
+
+use super::common::{SearchUsersQueryData, SuspendUserQueryData};
+
+/// returns the page of users matching all the set search fields, the latest created first
+pub async fn search_users(
+    app_data: web::Data<AppData>,
+    query_data: Query<SearchUsersQueryData>,
+) -> Result<HttpResponse, AppError> {
+    let user_service = app_data.user_service.lock()?;
+    let (users, total) = user_service
+        .search_users(
+            &query_data.to_user_search(),
+            query_data.page,
+            query_data.per_page,
+        )
+        .await?;
+    let users: Vec<_> = users.iter().map(|user| user.to_json()).collect();
+    Ok(HttpResponse::Ok().json(json!({
+        "users": users,
+        "total": total,
+        "page": query_data.page,
+        "per_page": query_data.per_page,
+    })))
+}
+
+/// returns the user with linked profiles and active sessions
+pub async fn get_user(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let user_id = match ObjectId::parse_str(path.into_inner()) {
+        Ok(user_id) => user_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().json(error_as_json(BAD_USER_ID))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&user_id).await? {
+        Some(user) => user,
+        None => return Ok(HttpResponse::NotFound().json(error_as_json(NO_USER_FOUND))),
+    };
+    let mut sessions: Vec<SessionInfo> = user_service
+        .get_all_user_sessions(&user.id)
+        .await?
+        .into_iter()
+        .map(|s| SessionInfo::new(s, &session.id))
+        .collect();
+    sessions.sort_by(|a, b| b.last_activity_at.cmp(&a.last_activity_at));
+    Ok(HttpResponse::Ok().json(json!({
+        "user": user.to_json(),
+        "sessions": sessions,
+    })))
+}
+
+/// suspends the user and removes all the user sessions, suspended user can't login
+pub async fn suspend_user(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    query_data: Json<SuspendUserQueryData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let user_id = match ObjectId::parse_str(path.into_inner()) {
+        Ok(user_id) => user_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().json(error_as_json(BAD_USER_ID))),
+    };
+    if query_data.until.is_some_and(|until| until <= Utc::now()) {
+        return Ok(HttpResponse::BadRequest().json(error_as_json(SUSPENSION_END_IN_PAST)));
+    }
+    let suspension = Suspension::new(query_data.reason.clone(), query_data.until, session.user_id);
+    let mut user_service = app_data.user_service.lock()?;
+    match user_service.suspend_user(&user_id, suspension).await {
+        Ok(user) => {
+            log::info!("User {} suspended by {}", &user.id, &session.user_id);
+            Ok(HttpResponse::Ok().json(user.to_json()))
+        }
+        Err(UserServiceError::UserNotFoundError) => {
+            Ok(HttpResponse::NotFound().json(error_as_json(NO_USER_FOUND)))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// lifts the user suspension
+pub async fn reactivate_user(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let user_id = match ObjectId::parse_str(path.into_inner()) {
+        Ok(user_id) => user_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().json(error_as_json(BAD_USER_ID))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    match user_service
+        .reactivate_user(&user_id, &session.user_id)
+        .await
+    {
+        Ok(user) => {
+            log::info!("User {} reactivated by {}", &user.id, &session.user_id);
+            Ok(HttpResponse::Ok().json(user.to_json()))
+        }
+        Err(UserServiceError::UserNotFoundError) => {
+            Ok(HttpResponse::NotFound().json(error_as_json(NO_USER_FOUND)))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// logs the user out of all devices and providers
+pub async fn force_logout(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let user_id = match ObjectId::parse_str(path.into_inner()) {
+        Ok(user_id) => user_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().json(error_as_json(BAD_USER_ID))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    user_service.logout_everywhere(&user_id).await?;
+    log::info!("User {} logged out by {}", &user_id, &session.user_id);
+    Ok(HttpResponse::Ok().json(result_as_json(SUCCESS)))
+}
+
+/// sends one-time password reset token to the user verified email or phone
+pub async fn trigger_password_reset(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let user_id = match ObjectId::parse_str(path.into_inner()) {
+        Ok(user_id) => user_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().json(error_as_json(BAD_USER_ID))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&user_id).await? {
+        Some(user) => user,
+        None => return Ok(HttpResponse::NotFound().json(error_as_json(NO_USER_FOUND))),
+    };
+    let profile = match &user.cyber_sherlock {
+        Some(profile) => profile,
+        None => {
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_CYBER_SHERLOCK_PROFILE)))
+        }
+    };
+    let mut cyber_sherlock_auth_provider = app_data.cyber_sherlock_auth_provider.lock()?;
+    if !cyber_sherlock_auth_provider.request_password_reset(profile)? {
+        return Ok(HttpResponse::BadRequest().json(error_as_json(NO_VERIFIED_CONTACT)));
+    }
+    log::info!(
+        "Password reset of User {} requested by {}",
+        &user.id,
+        &session.user_id
+    );
+    Ok(HttpResponse::Ok().json(result_as_json(PASSWORD_RESET_SENT)))
+}
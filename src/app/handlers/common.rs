@@ -5,4 +5,32 @@ pub mod response {
     pub const USER_SHOULD_RELOGIN: &str = "User should relogin";
     pub const WRONG_CREDENTIALS: &str = "Wrong credentials";
     pub const USER_WITH_THE_CREDENTIALS_EXISTS: &str = "User with the credentials exists";
+    pub const PROVIDER_IS_NOT_LINKED: &str = "Provider is not linked to the user";
+    pub const LAST_LOGIN_METHOD: &str = "Unable to remove the last login method";
+    pub const ACCOUNT_MERGE_CONFIRMATION_REQUIRED: &str =
+        "Account with the email exists, merge confirmation required";
+    pub const INVALID_MERGE_TOKEN: &str = "Invalid or expired merge token";
+    pub const PROVIDER_IS_ALREADY_LINKED: &str = "Provider is already linked to the user";
+    pub const BAD_USER_ID: &str = "Invalid user id";
+    pub const MERGE_USER_INTO_ITSELF: &str = "Unable to merge user into itself";
+    pub const NO_VERIFIED_EMAIL: &str = "User has no verified email";
+    pub const INVALID_EXPORT_TOKEN: &str = "Invalid or expired export link";
+    pub const EXPORT_LINK_SENT: &str = "Export link is sent to the user email";
+    pub const NOTHING_TO_UPDATE: &str = "Nothing to update";
+    pub const PROFILE_IS_READ_ONLY: &str =
+        "Only CyberSherlock profile is editable, provider profiles are read-only";
+    pub const INVALID_CONFIRMATION_CODE: &str = "Invalid or expired confirmation code";
+    pub const CONFIRMATION_CODE_SENT: &str = "Confirmation code is sent to the new destination";
+    pub const SESSION_NOT_FOUND: &str = "Session not found";
+    pub const NO_CYBER_SHERLOCK_PROFILE: &str = "User has no CyberSherlock profile";
+    pub const NO_VERIFIED_CONTACT: &str = "User has no verified email or phone";
+    pub const PASSWORD_RESET_SENT: &str = "Password reset token is sent to the user";
+    pub const INVALID_PASSWORD_RESET_TOKEN: &str = "Invalid or expired password reset token";
+    pub const SUSPENSION_END_IN_PAST: &str = "Suspension end has to be in the future";
+    pub const REAUTHENTICATION_REQUIRED: &str = "User should relogin to confirm the operation";
+    pub const NOT_AUTHENTICATED: &str = "User is not authenticated";
+    pub const AUTH_PROVIDER_REQUIRED: &str =
+        "User should login with another auth provider to access the resource";
+    pub const MFA_REQUIRED: &str = "Multi-factor authentication is required";
+    pub const PERMISSION_DENIED: &str = "User has no permission to access the resource";
 }
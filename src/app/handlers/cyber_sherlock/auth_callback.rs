@@ -6,12 +6,17 @@ use actix_web::{
 use crate::app::{
     app_data::AppData,
     app_error::AppError,
-    handlers::common::response::{SUCCESS, USER_SHOULD_RELOGIN},
+    handlers::common::response::{
+        ACCOUNT_MERGE_CONFIRMATION_REQUIRED, SUCCESS, USER_SHOULD_RELOGIN,
+    },
     models::{session::Session, user_profile::UserProfile},
     providers::{
         common::parse_callback_query_string, cyber_sherlock::provider::CyberSherlockAuthProvider,
     },
-    services::common::{error_as_json, result_as_json},
+    services::{
+        common::{error_as_json, merge_token_as_json},
+        user::error::UserServiceError,
+    },
 };
 
 pub async fn auth_callback(
@@ -32,29 +37,47 @@ pub async fn auth_callback(
         &register_cache_data.pkce_code_verifier,
         &callback_query_data.state,
     )?;
-    //TODO:
-    // - implement uniq key on user storage or other logic to avoid email or phone duplicates
-
+    // email and phone are uniq on user storage, duplicates get 409 on user inserting
     let user_profile = cyber_sherlock_auth_provider.create_user_profile(&register_cache_data)?;
 
-    let tokens = cyber_sherlock_auth_provider.get_tokens(&user_profile)?;
+    // registered user has no roles yet
+    let tokens = cyber_sherlock_auth_provider.get_tokens(&user_profile, &[])?;
 
-    if let Some(user_session) = user_service
+    let try_user_session = match user_service
         .get_user_session(
             tokens,
             UserProfile::CyberSherlock(user_profile.clone()),
             register_cache_data.session.metadata.clone(),
         )
-        .await?
+        .await
     {
+        Err(UserServiceError::MergeConfirmationRequired(merge_token)) => {
+            return Ok(HttpResponse::Conflict().json(merge_token_as_json(
+                ACCOUNT_MERGE_CONFIRMATION_REQUIRED,
+                &merge_token,
+            )));
+        }
+        result => result?,
+    };
+    if let Some(user_session) = try_user_session {
         log::debug!(
             "User {} loged in with CyberSherlock successfuly",
             &user_session.user_id
         );
-        let mut response = HttpResponse::Ok().json(result_as_json(SUCCESS));
-        user_service.set_session_cookie(response.head_mut(), &user_session)?;
+        // the callback is the browser redirect, the delivery is requested on register
+        let session_delivery = register_cache_data.session_delivery;
+        let mut response = HttpResponse::Ok().json(user_service.get_login_result_json(
+            SUCCESS,
+            session_delivery,
+            &user_session,
+        ));
         user_service
-            .remove_anonymous_sessions(vec![register_cache_data.session, session])
+            .rotate_session(
+                response.head_mut(),
+                session_delivery,
+                &user_session,
+                vec![register_cache_data.session, session],
+            )
             .await?;
 
         Ok(response)
@@ -4,17 +4,18 @@ use crate::app::{
     handlers::common::response::{NO_USER_FOUND, SUCCESS, WRONG_CREDENTIALS},
     models::{
         common::AuthProviders,
-        session::{NewSessionData, Session},
+        session::{NewSessionData, Session, SessionDelivery},
     },
     providers::cyber_sherlock::common::{verify_password, LoginQueryData},
-    services::common::{error_as_json, result_as_json},
+    services::common::error_as_json,
 };
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use actix_web_validator::Json;
 
 //TODO: check if session is not anonymous
 /// return Google Auth URL as json
 pub async fn login(
+    req: HttpRequest,
     app_data: web::Data<AppData>,
     login_query_data: Json<LoginQueryData>,
     session: Session,
@@ -28,24 +29,39 @@ pub async fn login(
         .await?
     {
         // validate login data password by profile hash
-        if let Some(cyber_sherlock_profile) = user.cyber_sherlock {
+        if let Some(cyber_sherlock_profile) = &user.cyber_sherlock {
             return match verify_password(&login_query_data.password, &cyber_sherlock_profile.hash) {
                 Ok(_) => {
                     let cyber_sherlock_auth_provider =
                         app_data.cyber_sherlock_auth_provider.lock()?;
-                    let tokens =
-                        cyber_sherlock_auth_provider.get_tokens(&cyber_sherlock_profile)?;
+                    let tokens = cyber_sherlock_auth_provider
+                        .get_tokens(&cyber_sherlock_profile, &user.roles)?;
                     let new_session = user_service
-                        .set_new_session(NewSessionData {
-                            anonimous: false,
-                            auth_provider: AuthProviders::CyberSherlock,
-                            user_id: user.id,
-                            tokens,
-                            session_metadata: session.metadata,
-                        })
+                        .set_new_session(
+                            &user,
+                            NewSessionData {
+                                anonimous: false,
+                                auth_provider: AuthProviders::CyberSherlock,
+                                user_id: user.id,
+                                tokens,
+                                session_metadata: session.metadata.clone(),
+                            },
+                        )
+                        .await?;
+                    let session_delivery = SessionDelivery::from_request(&req);
+                    let mut response = HttpResponse::Ok().json(user_service.get_login_result_json(
+                        SUCCESS,
+                        session_delivery,
+                        &new_session,
+                    ));
+                    user_service
+                        .rotate_session(
+                            response.head_mut(),
+                            session_delivery,
+                            &new_session,
+                            vec![session],
+                        )
                         .await?;
-                    let mut response = HttpResponse::Ok().json(result_as_json(SUCCESS));
-                    user_service.set_session_cookie(response.head_mut(), &new_session)?;
 
                     Ok(response)
                 }
@@ -2,3 +2,4 @@ pub mod auth_callback;
 pub mod confirm;
 pub mod login;
 pub mod register;
+pub mod reset_password;
@@ -2,11 +2,11 @@ use crate::app::{
     app_data::AppData,
     app_error::AppError,
     handlers::common::response::USER_WITH_THE_CREDENTIALS_EXISTS,
-    models::session::Session,
+    models::session::{Session, SessionDelivery},
     providers::cyber_sherlock::common::RegisterQueryData,
     services::common::{auth_url_as_json, error_as_json},
 };
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use actix_web_validator::Json;
 
 /// return CyberSherlock Auth URL as json
@@ -14,23 +14,31 @@ pub async fn register(
     app_data: web::Data<AppData>,
     query_data: Json<RegisterQueryData>,
     session: Session,
+    req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
+    // the anonymous session is kept in register cache data and removed on the callback
+    Session::require_persistence(&req)?;
     //TODO: check if it possible validate on request query parsing step
     query_data.validate()?;
 
-    // Check if user with the credentials exists
+    // Check if user with the credentials exists, it's an early check only
+    // uniqueness is enforced by user storage indexes on user inserting
     let user_service = app_data.user_service.lock()?;
     if user_service
         .find_user_by_credentials(&query_data.to_credentials())
         .await?
         .is_some()
     {
-        return Ok(HttpResponse::BadRequest().json(error_as_json(USER_WITH_THE_CREDENTIALS_EXISTS)));
+        return Ok(HttpResponse::Conflict().json(error_as_json(USER_WITH_THE_CREDENTIALS_EXISTS)));
     }
     // Generate the authorization URL and params to verify it in next
     let mut cyber_sherlock_auth_provider = app_data.cyber_sherlock_auth_provider.lock()?;
 
-    let auth_url = cyber_sherlock_auth_provider.get_authorization_url(&query_data, session)?;
+    let auth_url = cyber_sherlock_auth_provider.get_authorization_url(
+        &query_data,
+        session,
+        SessionDelivery::from_request(&req),
+    )?;
 
     Ok(HttpResponse::Ok().json(auth_url_as_json(&auth_url)))
 }
@@ -0,0 +1,27 @@
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{INVALID_PASSWORD_RESET_TOKEN, SUCCESS},
+    providers::cyber_sherlock::common::ResetPasswordQueryData,
+    services::common::{error_as_json, result_as_json},
+};
+use actix_web::{web, HttpResponse};
+use actix_web_validator::Json;
+
+/// sets the new password by the one-time reset token, the user is logged out everywhere
+pub async fn reset_password(
+    app_data: web::Data<AppData>,
+    query_data: Json<ResetPasswordQueryData>,
+) -> Result<HttpResponse, AppError> {
+    let cyber_sherlock_auth_provider = app_data.cyber_sherlock_auth_provider.lock()?;
+    let (user_id, hash) = match cyber_sherlock_auth_provider.reset_password(&query_data)? {
+        Some(password_reset) => password_reset,
+        None => {
+            return Ok(HttpResponse::BadRequest().json(error_as_json(INVALID_PASSWORD_RESET_TOKEN)))
+        }
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    let user = user_service.reset_password(&user_id, &hash).await?;
+    log::debug!("User {} password reset successfuly", &user.id);
+    Ok(HttpResponse::Ok().json(result_as_json(SUCCESS)))
+}
@@ -1,18 +1,24 @@
 use crate::app::{
-    app_data::AppData, app_error::AppError, models::session::Session,
+    app_data::AppData,
+    app_error::AppError,
+    models::session::{Session, SessionDelivery},
     services::common::auth_url_as_json,
 };
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 
 /// return Facebook Auth URL as json
 pub async fn login(
     app_data: web::Data<AppData>,
     session: Session,
+    req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
+    // the anonymous session is kept in login cache data and removed on the callback
+    Session::require_persistence(&req)?;
     // Generate the authorization URL and params to verify it in next
     let mut facebook_provider = app_data.facebook_provider.lock()?;
 
-    let auth_url = facebook_provider.get_authorization_url_data(session)?;
+    let auth_url = facebook_provider
+        .get_authorization_url_data(session, SessionDelivery::from_request(&req))?;
 
     Ok(HttpResponse::Ok().json(auth_url_as_json(&auth_url)))
 }
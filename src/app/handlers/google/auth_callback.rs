@@ -6,10 +6,15 @@ use actix_web::{
 use crate::app::{
     app_data::AppData,
     app_error::AppError,
-    handlers::common::response::{SUCCESS, USER_SHOULD_RELOGIN},
+    handlers::common::response::{
+        ACCOUNT_MERGE_CONFIRMATION_REQUIRED, SUCCESS, USER_SHOULD_RELOGIN,
+    },
     models::{session::Session, user_profile::UserProfile},
     providers::common::parse_callback_query_string,
-    services::common::{error_as_json, result_as_json},
+    services::{
+        common::{error_as_json, merge_token_as_json},
+        user::error::UserServiceError,
+    },
 };
 
 pub async fn auth_callback(
@@ -35,22 +40,41 @@ pub async fn auth_callback(
         .get_user_profile(tokens.access_token.clone())
         .await?;
 
-    if let Some(user_session) = user_service
+    let try_user_session = match user_service
         .get_user_session(
             tokens.clone(),
             UserProfile::Google(user_profile.clone()),
             login_cache_data.session.metadata.clone(),
         )
-        .await?
+        .await
     {
+        Err(UserServiceError::MergeConfirmationRequired(merge_token)) => {
+            return Ok(HttpResponse::Conflict().json(merge_token_as_json(
+                ACCOUNT_MERGE_CONFIRMATION_REQUIRED,
+                &merge_token,
+            )));
+        }
+        result => result?,
+    };
+    if let Some(user_session) = try_user_session {
         log::debug!(
             "User {} loged in with Google successfuly",
             &user_session.user_id
         );
-        let mut response = HttpResponse::Ok().json(result_as_json(SUCCESS));
-        user_service.set_session_cookie(response.head_mut(), &user_session)?;
+        // the callback is the browser redirect, the delivery is requested on login
+        let session_delivery = login_cache_data.session_delivery;
+        let mut response = HttpResponse::Ok().json(user_service.get_login_result_json(
+            SUCCESS,
+            session_delivery,
+            &user_session,
+        ));
         user_service
-            .remove_anonymous_sessions(vec![login_cache_data.session, session])
+            .rotate_session(
+                response.head_mut(),
+                session_delivery,
+                &user_session,
+                vec![login_cache_data.session, session],
+            )
             .await?;
 
         Ok(response)
@@ -1,14 +1,20 @@
 use crate::app::{
-    app_data::AppData, app_error::AppError, models::session::Session,
-    providers::common::LoginCacheData, services::common::auth_url_as_json,
+    app_data::AppData,
+    app_error::AppError,
+    models::session::{Session, SessionDelivery},
+    providers::common::LoginCacheData,
+    services::common::auth_url_as_json,
 };
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 
 /// return Google Auth URL as json
 pub async fn login(
     app_data: web::Data<AppData>,
     session: Session,
+    req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
+    // the anonymous session is kept in login cache data and removed on the callback
+    Session::require_persistence(&req)?;
     // Generate the authorization URL and params to verify it in next
     let mut google_provider = app_data.google_provider.lock()?;
     let (authorize_url, csrf_state, pkce_code_verifier) =
@@ -18,6 +24,7 @@ pub async fn login(
     let login_cache_data = LoginCacheData {
         pkce_code_verifier,
         session,
+        session_delivery: SessionDelivery::from_request(&req),
     };
     google_provider.set_auth_data_to_cache(csrf_state.secret().as_ref(), &login_cache_data)?;
 
@@ -1,7 +1,16 @@
-use actix_web::HttpResponse;
+use actix_web::{http::header::ContentType, HttpResponse};
+
+use crate::app::shared::metrics::render_metrics;
 
 use super::common::response::SUCCESS;
 
 pub async fn status() -> HttpResponse {
     HttpResponse::Ok().body(SUCCESS)
 }
+
+/// service counters in Prometheus text format
+pub async fn metrics() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::plaintext())
+        .body(render_metrics())
+}
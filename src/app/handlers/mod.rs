@@ -1,3 +1,4 @@
+pub mod admin;
 pub mod common;
 pub mod cyber_sherlock;
 pub mod facebook;
@@ -5,3 +6,4 @@ pub mod google;
 pub mod health_check;
 pub mod logout;
 pub mod me;
+pub mod user;
@@ -0,0 +1,36 @@
+use std::str::FromStr;
+
+use actix_web::{web, HttpResponse};
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{NO_USER_FOUND, PROVIDER_IS_NOT_LINKED},
+    models::{authenticated_session::AuthenticatedSession, common::AuthProviders},
+    services::common::error_as_json,
+};
+
+/// set the user active profile to one of linked providers
+pub async fn set_active_profile(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let provider = match AuthProviders::from_str(&path.into_inner()) {
+        Ok(p) => p,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(error_as_json(err))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&session.user_id).await? {
+        Some(user) => user,
+        None => {
+            log::debug!("No User found by Session {:?}", &session);
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_USER_FOUND)));
+        }
+    };
+    if !user.has_profile(&provider) {
+        return Ok(HttpResponse::BadRequest().json(error_as_json(PROVIDER_IS_NOT_LINKED)));
+    }
+    let updated_user = user_service.set_active_profile(&user.id, provider).await?;
+    Ok(HttpResponse::Ok().json(updated_user.to_json()))
+}
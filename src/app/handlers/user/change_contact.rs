@@ -0,0 +1,122 @@
+use actix_web::{web, HttpResponse};
+use actix_web_validator::Json;
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{
+        CONFIRMATION_CODE_SENT, INVALID_CONFIRMATION_CODE, NO_CYBER_SHERLOCK_PROFILE,
+        NO_USER_FOUND, PROFILE_IS_READ_ONLY, USER_WITH_THE_CREDENTIALS_EXISTS,
+    },
+    models::authenticated_session::AuthenticatedSession,
+    providers::cyber_sherlock::common::{
+        ChangeEmailQueryData, ChangePhoneQueryData, ConfirmContactChangeQueryData, ContactChange,
+        Credentials,
+    },
+    services::common::{error_as_json, result_as_json},
+};
+
+/// sends confirmation code to the new CyberSherlock user email
+pub async fn change_email(
+    app_data: web::Data<AppData>,
+    query_data: Json<ChangeEmailQueryData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let credentials = Credentials {
+        email: Some(query_data.email.clone()),
+        phone: None,
+    };
+    request_contact_change(
+        app_data,
+        session,
+        credentials,
+        ContactChange::Email(query_data.email.clone()),
+    )
+    .await
+}
+
+/// sends confirmation code to the new CyberSherlock user phone
+pub async fn change_phone(
+    app_data: web::Data<AppData>,
+    query_data: Json<ChangePhoneQueryData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let credentials = Credentials {
+        email: None,
+        phone: Some(query_data.phone.clone()),
+    };
+    request_contact_change(
+        app_data,
+        session,
+        credentials,
+        ContactChange::Phone(query_data.phone.clone()),
+    )
+    .await
+}
+
+/// sets confirmed email or phone to CyberSherlock user profile as verified
+pub async fn confirm_contact_change(
+    app_data: web::Data<AppData>,
+    query_data: Json<ConfirmContactChangeQueryData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&session.user_id).await? {
+        Some(user) => user,
+        None => {
+            log::debug!("No User found by Session {:?}", &session);
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_USER_FOUND)));
+        }
+    };
+    // the profile could be unlinked after the change request,
+    // contact update of not existing profile would create a broken one
+    if user.cyber_sherlock.is_none() {
+        return Ok(HttpResponse::BadRequest().json(error_as_json(NO_CYBER_SHERLOCK_PROFILE)));
+    }
+    let mut cyber_sherlock_auth_provider = app_data.cyber_sherlock_auth_provider.lock()?;
+    let change = match cyber_sherlock_auth_provider
+        .confirm_contact_change(&session.user_id, &query_data.code)?
+    {
+        Some(change) => change,
+        None => {
+            return Ok(HttpResponse::BadRequest().json(error_as_json(INVALID_CONFIRMATION_CODE)))
+        }
+    };
+    // email and phone are uniq on user storage, duplicates get 409
+    let updated_user = user_service
+        .update_cyber_sherlock_contact(&session.user_id, change)
+        .await?;
+    log::debug!("User {} contact changed successfuly", &updated_user.id);
+    Ok(HttpResponse::Ok().json(updated_user.to_json()))
+}
+
+async fn request_contact_change(
+    app_data: web::Data<AppData>,
+    session: AuthenticatedSession,
+    credentials: Credentials,
+    change: ContactChange,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&session.user_id).await? {
+        Some(user) => user,
+        None => {
+            log::debug!("No User found by Session {:?}", &session);
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_USER_FOUND)));
+        }
+    };
+    let user_profile = match user.cyber_sherlock {
+        Some(profile) => profile,
+        None => return Ok(HttpResponse::BadRequest().json(error_as_json(PROFILE_IS_READ_ONLY))),
+    };
+    // it's an early check only, uniqueness is enforced on the change confirmation
+    if user_service
+        .find_user_by_credentials(&credentials)
+        .await?
+        .is_some()
+    {
+        return Ok(HttpResponse::Conflict().json(error_as_json(USER_WITH_THE_CREDENTIALS_EXISTS)));
+    }
+    let mut cyber_sherlock_auth_provider = app_data.cyber_sherlock_auth_provider.lock()?;
+    cyber_sherlock_auth_provider.request_contact_change(&user_profile, change)?;
+    Ok(HttpResponse::Accepted().json(result_as_json(CONFIRMATION_CODE_SENT)))
+}
@@ -0,0 +1,137 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    models::{common::AuthProviders, user::User, user_profile::CyberSherlockProfileUpdate},
+    services::user::service::UserService,
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct ConfirmMergeQueryData {
+    #[validate(length(min = 1, max = 128))]
+    pub merge_token: String,
+}
+
+// BCP 47 language tag, e.g. `en`, `en-US`, `zh-Hant-TW`
+const LOCALE_PATTERN: &str = r"^[a-zA-Z]{2,3}(-[a-zA-Z0-9]{2,8})*$";
+// IANA time zone name, e.g. `UTC`, `Europe/Kyiv`, `America/Argentina/Buenos_Aires`
+const TIMEZONE_PATTERN: &str = r"^[A-Za-z][A-Za-z0-9_+\-]*(/[A-Za-z0-9_+\-]+)*$";
+
+// name is stored trimmed, so the length is validated on the trimmed one
+fn validate_name(name: &str) -> Result<(), ValidationError> {
+    match name.trim().chars().count() {
+        1..=100 => Ok(()),
+        _ => Err(ValidationError::new(
+            "Invalid `name`, it has to be 1-100 characters without surrounding spaces",
+        )),
+    }
+}
+
+fn validate_locale(locale: &str) -> Result<(), ValidationError> {
+    validate_pattern(
+        locale,
+        LOCALE_PATTERN,
+        "Invalid `locale`, it has to be BCP 47 tag",
+    )
+}
+
+fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+    if timezone.len() > 64 {
+        return Err(ValidationError::new("Invalid `timezone`, it is too long"));
+    }
+    validate_pattern(
+        timezone,
+        TIMEZONE_PATTERN,
+        "Invalid `timezone`, it has to be IANA time zone name",
+    )
+}
+
+fn validate_pattern(
+    value: &str,
+    pattern: &str,
+    error_code: &'static str,
+) -> Result<(), ValidationError> {
+    match Regex::new(pattern) {
+        Ok(regex) if regex.is_match(value) => Ok(()),
+        _ => Err(ValidationError::new(error_code)),
+    }
+}
+
+/// CyberSherlock profile fields editable by the user
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct UpdateProfileData {
+    #[validate(custom = "validate_name")]
+    pub name: Option<String>,
+    #[validate(url, length(max = 2048))]
+    pub picture: Option<String>,
+    #[validate(custom = "validate_locale")]
+    pub locale: Option<String>,
+    #[validate(custom = "validate_timezone")]
+    pub timezone: Option<String>,
+}
+
+impl UpdateProfileData {
+    pub fn to_profile_update(&self) -> CyberSherlockProfileUpdate {
+        CyberSherlockProfileUpdate {
+            name: self.name.as_ref().map(|name| name.trim().to_string()),
+            picture: self.picture.clone(),
+            locale: self.locale.clone(),
+            timezone: self.timezone.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportDelivery {
+    #[default]
+    Download,
+    Email,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct ExportQueryData {
+    #[serde(default)]
+    pub delivery: ExportDelivery,
+}
+
+/// revokes user tokens on the provider side,
+/// revocation fails are logged only, user can revoke app access on the provider side anyway
+pub async fn revoke_provider_tokens(
+    app_data: &AppData,
+    user_service: &mut UserService,
+    user: &User,
+    provider: AuthProviders,
+) -> Result<(), AppError> {
+    match provider {
+        AuthProviders::Google => {
+            let sessions = user_service
+                .get_user_sessions_by_provider(&user.id, provider)
+                .await?;
+            // Google keeps the same refresh token for all user sessions
+            if let Some(token) = sessions.into_iter().find_map(|s| s.tokens.refresh_token) {
+                let google_provider = app_data.google_provider.lock()?;
+                if let Err(err) = google_provider.revoke_token(&token.token_string).await {
+                    log::warn!(
+                        "Unable to revoke Google token of User {}: {}",
+                        &user.id,
+                        err
+                    );
+                }
+            }
+        }
+        AuthProviders::Facebook => {
+            if let Some(facebook_profile) = &user.facebook {
+                let mut facebook_provider = app_data.facebook_provider.lock()?;
+                if let Err(err) = facebook_provider.logout(&facebook_profile.user_id).await {
+                    log::warn!("Unable to logout Facebook User {}: {}", &user.id, err);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use actix_web::{web, HttpResponse};
+use actix_web_validator::Json;
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{INVALID_MERGE_TOKEN, NO_USER_FOUND, PROVIDER_IS_ALREADY_LINKED},
+    models::{
+        authenticated_session::AuthenticatedSession,
+        user_audit::{UserAuditEvent, UserAuditRecord},
+        user_profile::UserProfile,
+    },
+    services::common::error_as_json,
+};
+
+use super::common::ConfirmMergeQueryData;
+
+/// attaches pending provider profile to the current user,
+/// user has to be logged in to the account the profile was matched with
+pub async fn confirm_merge(
+    app_data: web::Data<AppData>,
+    query_data: Json<ConfirmMergeQueryData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    let pending_merge =
+        match user_service.take_pending_merge(&session.user_id, &query_data.merge_token)? {
+            Some(pending_merge) => pending_merge,
+            None => return Ok(HttpResponse::BadRequest().json(error_as_json(INVALID_MERGE_TOKEN))),
+        };
+    let user = match user_service.get_user_by_id(&session.user_id).await? {
+        Some(user) => user,
+        None => {
+            log::debug!("No User found by Session {:?}", &session);
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_USER_FOUND)));
+        }
+    };
+    let provider = UserProfile::get_provider(&pending_merge.profile);
+    // user has to unlink the provider profile before
+    if user.has_profile(&provider) {
+        return Ok(HttpResponse::Conflict().json(error_as_json(PROVIDER_IS_ALREADY_LINKED)));
+    }
+    let updated_user = user_service
+        .link_user_profile(
+            &user,
+            pending_merge.profile,
+            UserAuditRecord::new(UserAuditEvent::ProfileMergeConfirmed, provider, None),
+        )
+        .await?;
+    log::info!(
+        "{} profile merge confirmed by User {}",
+        provider.to_string(),
+        &updated_user.id
+    );
+    Ok(HttpResponse::Ok().json(updated_user.to_json()))
+}
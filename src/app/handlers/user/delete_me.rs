@@ -0,0 +1,42 @@
+use actix_web::{web, HttpResponse};
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{NO_USER_FOUND, SUCCESS},
+    models::authenticated_session::AuthenticatedSession,
+    services::common::{error_as_json, result_as_json},
+};
+
+use super::common::revoke_provider_tokens;
+
+/// deletes the user account(GDPR), the route requires recent login,
+/// in case of deletion grace period the account is restorable by login till purge_at
+pub async fn delete_me(
+    app_data: web::Data<AppData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&session.user_id).await? {
+        Some(user) => user,
+        None => {
+            log::debug!("No User found by Session {:?}", &session);
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_USER_FOUND)));
+        }
+    };
+
+    for provider in user.get_linked_providers() {
+        revoke_provider_tokens(&app_data, &mut user_service, &user, provider).await?;
+    }
+
+    match user_service
+        .delete_user(&user, session.auth_provider)
+        .await?
+    {
+        Some(deleted_user) => {
+            log::debug!("User {} deletion scheduled", &deleted_user.id);
+            Ok(HttpResponse::Accepted().json(deleted_user.to_json()))
+        }
+        None => Ok(HttpResponse::Ok().json(result_as_json(SUCCESS))),
+    }
+}
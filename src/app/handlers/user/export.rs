@@ -0,0 +1,71 @@
+use actix_web::{
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web, HttpResponse,
+};
+use actix_web_validator::Query;
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{
+        EXPORT_LINK_SENT, INVALID_EXPORT_TOKEN, NO_USER_FOUND, NO_VERIFIED_EMAIL,
+    },
+    models::{authenticated_session::AuthenticatedSession, user_data_export::UserDataExport},
+    services::common::{error_as_json, result_as_json},
+};
+
+use super::common::{ExportDelivery, ExportQueryData};
+
+/// exports everything the service keeps about the user(GDPR),
+/// the export is downloaded directly or sent by time-limited link to the user verified email
+pub async fn export_me(
+    app_data: web::Data<AppData>,
+    query_data: Query<ExportQueryData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&session.user_id).await? {
+        Some(user) => user,
+        None => {
+            log::debug!("No User found by Session {:?}", &session);
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_USER_FOUND)));
+        }
+    };
+    let user_data_export = user_service.export_user_data(&user).await?;
+    match query_data.delivery {
+        ExportDelivery::Download => Ok(export_as_attachment(&user_data_export)),
+        ExportDelivery::Email => {
+            let email = match user.get_verified_email() {
+                Some(email) => email,
+                None => {
+                    return Ok(HttpResponse::BadRequest().json(error_as_json(NO_VERIFIED_EMAIL)))
+                }
+            };
+            let export_link = user_service.set_user_data_export(&user_data_export)?;
+            let mut notification_provider = app_data.notification_provider.lock()?;
+            notification_provider.send_email(&export_link, email)?;
+            Ok(HttpResponse::Accepted().json(result_as_json(EXPORT_LINK_SENT)))
+        }
+    }
+}
+
+/// downloads the export by emailed link, the link is valid once
+pub async fn download_export(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let user_service = app_data.user_service.lock()?;
+    match user_service.take_user_data_export(&path.into_inner())? {
+        Some(user_data_export) => Ok(export_as_attachment(&user_data_export)),
+        None => Ok(HttpResponse::NotFound().json(error_as_json(INVALID_EXPORT_TOKEN))),
+    }
+}
+
+fn export_as_attachment(user_data_export: &UserDataExport) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(user_data_export.get_file_name())],
+        })
+        .json(user_data_export)
+}
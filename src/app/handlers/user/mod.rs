@@ -0,0 +1,10 @@
+pub mod active_profile;
+pub mod change_contact;
+pub mod common;
+pub mod confirm_merge;
+pub mod delete_me;
+pub mod export;
+pub mod sessions;
+mod test;
+pub mod unlink_provider;
+pub mod update_me;
@@ -0,0 +1,74 @@
+use actix_web::{web, HttpResponse};
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{SESSION_NOT_FOUND, SUCCESS},
+    models::{authenticated_session::AuthenticatedSession, session::SessionInfo},
+    services::common::{error_as_json, result_as_json},
+};
+
+use super::common::revoke_provider_tokens;
+
+/// returns all active user sessions of all providers, the latest active first
+pub async fn get_sessions(
+    app_data: web::Data<AppData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    let mut sessions: Vec<SessionInfo> = user_service
+        .get_all_user_sessions(&session.user_id)
+        .await?
+        .into_iter()
+        .map(|s| SessionInfo::new(s, &session.id))
+        .collect();
+    sessions.sort_by(|a, b| b.last_activity_at.cmp(&a.last_activity_at));
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+/// revokes one user session by its public id
+pub async fn revoke_session(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    if user_service
+        .revoke_session(&session.user_id, &path.into_inner())
+        .await?
+    {
+        Ok(HttpResponse::Ok().json(result_as_json(SUCCESS)))
+    } else {
+        Ok(HttpResponse::NotFound().json(error_as_json(SESSION_NOT_FOUND)))
+    }
+}
+
+/// revokes all user sessions except the current one
+pub async fn revoke_other_sessions(
+    app_data: web::Data<AppData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    let removed_sessions = user_service.revoke_other_sessions(&session).await?;
+    log::debug!(
+        "{} sessions of User {} revoked",
+        removed_sessions,
+        &session.user_id
+    );
+    Ok(HttpResponse::Ok().json(result_as_json(SUCCESS)))
+}
+
+/// logs the user out of all devices and providers, provider tokens are revoked as well
+pub async fn logout_everywhere(
+    app_data: web::Data<AppData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let mut user_service = app_data.user_service.lock()?;
+    if let Some(user) = user_service.get_user_by_id(&session.user_id).await? {
+        for provider in user.get_linked_providers() {
+            revoke_provider_tokens(&app_data, &mut user_service, &user, provider).await?;
+        }
+    }
+    user_service.logout_everywhere(&session.user_id).await?;
+    Ok(HttpResponse::Ok().json(result_as_json(SUCCESS)))
+}
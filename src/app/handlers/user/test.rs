@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        dev::Service,
+        http::{header, StatusCode},
+        test, web, App, HttpMessage,
+    };
+    use bson::oid::ObjectId;
+    use validator::Validate;
+
+    use crate::{
+        app::{
+            app_data::AppData,
+            handlers::user::{
+                common::UpdateProfileData,
+                export::{download_export, export_me},
+            },
+            models::{
+                common::AuthProviders,
+                session::{NewSessionData, Session},
+                session_metadata::SessionMetadata,
+                user::User,
+                user_data_export::UserDataExport,
+                user_profile::UserProfile,
+            },
+        },
+        tests::test_data::TestData,
+    };
+
+    #[actix_rt::test]
+    async fn export_me_as_download() {
+        let (app_data, user, session) = intialize().await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_data.clone())
+                .wrap_fn({
+                    let session = session.clone();
+                    move |req, srv| {
+                        // it's set by SessionMiddleware in the app
+                        req.extensions_mut().insert(session.clone());
+                        srv.call(req)
+                    }
+                })
+                .route("/me/export", web::get().to(export_me)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/me/export?delivery=download")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content_disposition = match res.headers().get(header::CONTENT_DISPOSITION) {
+            Some(value) => value.to_str().unwrap_or_default().to_string(),
+            None => return assert!(false, "No Content-Disposition header in export response!"),
+        };
+        assert_eq!(content_disposition.starts_with("attachment"), true);
+        assert_eq!(
+            content_disposition.contains(&format!("user-data-{}.json", user.id)),
+            true
+        );
+
+        let user_data_export: UserDataExport = test::read_body_json(res).await;
+        assert_eq!(user_data_export.user_id, user.id);
+        assert_eq!(user_data_export.user["google"].is_object(), true);
+        assert_eq!(user_data_export.sessions.len(), 1);
+        assert_eq!(user_data_export.login_history.len(), 1);
+        assert_eq!(
+            user_data_export.login_history[0]
+                .auth_provider
+                .is_equal(&AuthProviders::Google),
+            true
+        );
+
+        clean_up(&app_data, &user).await;
+    }
+
+    #[actix_rt::test]
+    async fn export_me_without_session() {
+        let (app_data, user, _) = intialize().await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_data.clone())
+                .route("/me/export", web::get().to(export_me)),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/me/export").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        clean_up(&app_data, &user).await;
+    }
+
+    #[actix_rt::test]
+    async fn download_export_by_link_once() {
+        let (app_data, user, _) = intialize().await;
+
+        let export_link = {
+            let mut user_service = app_data.user_service.lock().unwrap();
+            let user_data_export = match user_service.export_user_data(&user).await {
+                Ok(user_data_export) => user_data_export,
+                Err(err) => return assert!(false, "{}", err.to_string()),
+            };
+            match user_service.set_user_data_export(&user_data_export) {
+                Ok(export_link) => export_link,
+                Err(err) => return assert!(false, "{}", err.to_string()),
+            }
+        };
+        let export_token = export_link.rsplit('/').next().unwrap_or_default();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_data.clone())
+                .route("/export/{export_token}", web::get().to(download_export)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/export/{}", export_token))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let user_data_export: UserDataExport = test::read_body_json(res).await;
+        assert_eq!(user_data_export.user_id, user.id);
+
+        // the link is valid once
+        let req = test::TestRequest::get()
+            .uri(&format!("/export/{}", export_token))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        clean_up(&app_data, &user).await;
+    }
+
+    #[actix_rt::test]
+    async fn update_profile_name_is_validated_trimmed() {
+        let update_profile_data = |name: &str| UpdateProfileData {
+            name: Some(name.to_string()),
+            picture: None,
+            locale: None,
+            timezone: None,
+        };
+
+        assert_eq!(update_profile_data("Sherlock").validate().is_ok(), true);
+        assert_eq!(update_profile_data("  Sherlock  ").validate().is_ok(), true);
+        assert_eq!(update_profile_data("").validate().is_err(), true);
+        assert_eq!(update_profile_data("   ").validate().is_err(), true);
+        assert_eq!(
+            update_profile_data(&format!(" {} ", "a".repeat(100)))
+                .validate()
+                .is_ok(),
+            true
+        );
+        assert_eq!(
+            update_profile_data(&"a".repeat(101)).validate().is_err(),
+            true
+        );
+    }
+
+    async fn intialize() -> (web::Data<AppData>, User, Session) {
+        let app_data = web::Data::new(AppData::new().await.expect("Error to create AppData"));
+
+        let mut google_profile = match TestData::new().user.google {
+            Some(google_profile) => google_profile,
+            None => panic!("Bad test user google profile!"),
+        };
+        // uniq email, the user must not be merged with other test users by verified email
+        google_profile.email = format!("fake_export_{}@gmail.com", ObjectId::new());
+
+        let mut user_service = app_data.user_service.lock().unwrap();
+        let user = user_service
+            .create_user_with_profile(UserProfile::Google(google_profile))
+            .await
+            .expect("Error to create test user");
+        let session = user_service
+            .set_new_session(
+                &user,
+                NewSessionData {
+                    anonimous: false,
+                    auth_provider: AuthProviders::Google,
+                    user_id: user.id,
+                    tokens: TestData::new().google_session.tokens,
+                    session_metadata: SessionMetadata::new(),
+                },
+            )
+            .await
+            .expect("Error to create test session");
+        drop(user_service);
+
+        (app_data, user, session)
+    }
+
+    async fn clean_up(app_data: &web::Data<AppData>, user: &User) {
+        let mut user_service = app_data.user_service.lock().unwrap();
+        let result = user_service.delete_user(user, AuthProviders::Google).await;
+        assert_eq!(result.is_ok(), true);
+    }
+}
@@ -0,0 +1,51 @@
+use std::str::FromStr;
+
+use actix_web::{web, HttpResponse};
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{LAST_LOGIN_METHOD, NO_USER_FOUND, PROVIDER_IS_NOT_LINKED},
+    models::{authenticated_session::AuthenticatedSession, common::AuthProviders},
+    services::common::error_as_json,
+};
+
+use super::common::revoke_provider_tokens;
+
+/// removes provider profile from the user, revokes provider tokens
+/// and deletes user sessions of the provider
+pub async fn unlink_provider(
+    app_data: web::Data<AppData>,
+    path: web::Path<String>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let provider = match AuthProviders::from_str(&path.into_inner()) {
+        Ok(p) => p,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(error_as_json(err))),
+    };
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&session.user_id).await? {
+        Some(user) => user,
+        None => {
+            log::debug!("No User found by Session {:?}", &session);
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_USER_FOUND)));
+        }
+    };
+    if !user.has_profile(&provider) {
+        return Ok(HttpResponse::BadRequest().json(error_as_json(PROVIDER_IS_NOT_LINKED)));
+    }
+    // user has to keep at least one login method
+    if user.get_linked_providers().len() < 2 {
+        return Ok(HttpResponse::Conflict().json(error_as_json(LAST_LOGIN_METHOD)));
+    }
+
+    revoke_provider_tokens(&app_data, &mut user_service, &user, provider).await?;
+
+    let updated_user = user_service.unlink_provider(&user, provider).await?;
+    log::debug!(
+        "User {} unlinked {} profile successfuly",
+        &updated_user.id,
+        provider.to_string()
+    );
+    Ok(HttpResponse::Ok().json(updated_user.to_json()))
+}
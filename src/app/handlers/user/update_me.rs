@@ -0,0 +1,39 @@
+use actix_web::{web, HttpResponse};
+use actix_web_validator::Json;
+
+use crate::app::{
+    app_data::AppData,
+    app_error::AppError,
+    handlers::common::response::{NOTHING_TO_UPDATE, NO_USER_FOUND, PROFILE_IS_READ_ONLY},
+    models::{authenticated_session::AuthenticatedSession, common::AuthProviders},
+    services::common::error_as_json,
+};
+
+use super::common::UpdateProfileData;
+
+/// updates the user CyberSherlock profile, Google and Facebook profiles are owned by providers
+pub async fn update_me(
+    app_data: web::Data<AppData>,
+    query_data: Json<UpdateProfileData>,
+    session: AuthenticatedSession,
+) -> Result<HttpResponse, AppError> {
+    let profile_update = query_data.to_profile_update();
+    if profile_update.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(error_as_json(NOTHING_TO_UPDATE)));
+    }
+    let mut user_service = app_data.user_service.lock()?;
+    let user = match user_service.get_user_by_id(&session.user_id).await? {
+        Some(user) => user,
+        None => {
+            log::debug!("No User found by Session {:?}", &session);
+            return Ok(HttpResponse::BadRequest().json(error_as_json(NO_USER_FOUND)));
+        }
+    };
+    if !user.has_profile(&AuthProviders::CyberSherlock) {
+        return Ok(HttpResponse::BadRequest().json(error_as_json(PROFILE_IS_READ_ONLY)));
+    }
+    let updated_user = user_service
+        .update_cyber_sherlock_profile(&user.id, profile_update)
+        .await?;
+    Ok(HttpResponse::Ok().json(updated_user.to_json()))
+}
@@ -0,0 +1,179 @@
+use std::{future::Future, pin::Pin, rc::Rc};
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    HttpMessage, ResponseError,
+};
+use chrono::{Duration, Utc};
+use thiserror::Error;
+
+use crate::app::{
+    app_error::AppError,
+    handlers::common::response::{
+        AUTH_PROVIDER_REQUIRED, MFA_REQUIRED, NOT_AUTHENTICATED, PERMISSION_DENIED,
+        REAUTHENTICATION_REQUIRED,
+    },
+    models::{common::AuthProviders, session::Session},
+};
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("User is not authenticated")]
+    NotAuthenticated,
+
+    #[error("User should login with {0:?} to access the resource")]
+    AuthProviderRequired(AuthProviders),
+
+    #[error("Multi-factor authentication is required")]
+    MfaRequired,
+
+    #[error("User should relogin to confirm the operation")]
+    ReauthenticationRequired,
+
+    #[error("Permission {0} is required")]
+    PermissionDenied(String),
+}
+
+impl AuthError {
+    /// response error message, the error details are logged only
+    pub fn get_message(&self) -> &'static str {
+        match self {
+            AuthError::NotAuthenticated => NOT_AUTHENTICATED,
+            AuthError::AuthProviderRequired(_) => AUTH_PROVIDER_REQUIRED,
+            AuthError::MfaRequired => MFA_REQUIRED,
+            AuthError::ReauthenticationRequired => REAUTHENTICATION_REQUIRED,
+            AuthError::PermissionDenied(_) => PERMISSION_DENIED,
+        }
+    }
+}
+
+/// what the session has to satisfy to access the resource, anonymous session never does,
+/// it's set to scope or resource app data for `AuthenticatedSession` extractor
+/// or passed to `AuthGuard` middleware
+#[derive(Clone, Debug, Default)]
+pub struct AuthRequirements {
+    auth_provider: Option<AuthProviders>,
+    mfa: bool,
+    // max seconds since login
+    max_auth_age_sec: Option<i64>,
+}
+
+impl AuthRequirements {
+    pub fn new() -> Self {
+        AuthRequirements::default()
+    }
+
+    pub fn auth_provider(mut self, auth_provider: AuthProviders) -> Self {
+        self.auth_provider = Some(auth_provider);
+        self
+    }
+
+    pub fn mfa(mut self) -> Self {
+        self.mfa = true;
+        self
+    }
+
+    pub fn max_auth_age(mut self, max_auth_age_sec: i64) -> Self {
+        self.max_auth_age_sec = Some(max_auth_age_sec);
+        self
+    }
+
+    pub fn check(&self, session: &Session) -> Result<(), AuthError> {
+        if session.is_anonymous() {
+            return Err(AuthError::NotAuthenticated);
+        }
+        if let Some(auth_provider) = &self.auth_provider {
+            if !session.auth_provider.is_equal(auth_provider) {
+                return Err(AuthError::AuthProviderRequired(*auth_provider));
+            }
+        }
+        if self.mfa && session.mfa_completed_at.is_none() {
+            return Err(AuthError::MfaRequired);
+        }
+        if let Some(max_auth_age_sec) = self.max_auth_age_sec {
+            if Utc::now() - session.created_at > Duration::seconds(max_auth_age_sec) {
+                return Err(AuthError::ReauthenticationRequired);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// rejects the scope requests which session doesn't satisfy the requirements with 401,
+/// it has to be wrapped by `SessionMiddleware`
+pub struct AuthGuard {
+    requirements: Rc<AuthRequirements>,
+}
+
+impl AuthGuard {
+    pub fn new(requirements: AuthRequirements) -> Self {
+        Self {
+            requirements: Rc::new(requirements),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AuthGuard
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = InnerAuthGuard<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(InnerAuthGuard {
+            service: Rc::new(service),
+            requirements: Rc::clone(&self.requirements),
+        }))
+    }
+}
+
+pub struct InnerAuthGuard<S> {
+    service: Rc<S>,
+    requirements: Rc<AuthRequirements>,
+}
+
+impl<S, B> Service<ServiceRequest> for InnerAuthGuard<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let requirements = Rc::clone(&self.requirements);
+
+        Box::pin(async move {
+            let check_result = match req.extensions().get::<Session>() {
+                Some(session) => requirements.check(session),
+                None => {
+                    log::error!("No Session on Auth Guard, SessionMiddleware has to wrap it");
+                    Err(AuthError::NotAuthenticated)
+                }
+            };
+            if let Err(err) = check_result {
+                log::debug!("Auth Guard rejected request to {}: {}", req.path(), err);
+                let err_response = AppError::from(err).error_response();
+                let (http_req, _) = req.into_parts();
+                return Ok(ServiceResponse::new(http_req, err_response).map_into_right_body());
+            }
+            let res = service.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+mod test;
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use crate::{
+        app::{
+            middlewares::auth_guard::{AuthError, AuthRequirements},
+            models::{common::AuthProviders, session::Session, session_metadata::SessionMetadata},
+        },
+        tests::test_data::TestData,
+    };
+
+    #[test]
+    fn anonymous_session_is_rejected() {
+        let session = Session::get_anonymous_session(SessionMetadata::new());
+
+        assert!(matches!(
+            AuthRequirements::new().check(&session),
+            Err(AuthError::NotAuthenticated)
+        ));
+        // anonymous session never satisfies the requirements
+        assert!(matches!(
+            AuthRequirements::new()
+                .auth_provider(AuthProviders::CyberSherlock)
+                .check(&session),
+            Err(AuthError::NotAuthenticated)
+        ));
+    }
+
+    #[test]
+    fn auth_provider_is_required() {
+        let session = TestData::new().google_session;
+
+        assert_eq!(
+            AuthRequirements::new()
+                .auth_provider(AuthProviders::Google)
+                .check(&session)
+                .is_ok(),
+            true
+        );
+        assert!(matches!(
+            AuthRequirements::new()
+                .auth_provider(AuthProviders::CyberSherlock)
+                .check(&session),
+            Err(AuthError::AuthProviderRequired(
+                AuthProviders::CyberSherlock
+            ))
+        ));
+    }
+
+    #[test]
+    fn mfa_is_required() {
+        let mut session = TestData::new().google_session;
+
+        assert!(matches!(
+            AuthRequirements::new().mfa().check(&session),
+            Err(AuthError::MfaRequired)
+        ));
+        session.mfa_completed_at = Some(Utc::now());
+        assert_eq!(AuthRequirements::new().mfa().check(&session).is_ok(), true);
+    }
+
+    #[test]
+    fn recent_auth_is_required() {
+        let mut session = TestData::new().google_session;
+
+        assert_eq!(
+            AuthRequirements::new()
+                .max_auth_age(60)
+                .check(&session)
+                .is_ok(),
+            true
+        );
+        session.created_at = Utc::now() - Duration::seconds(120);
+        assert!(matches!(
+            AuthRequirements::new().max_auth_age(60).check(&session),
+            Err(AuthError::ReauthenticationRequired)
+        ));
+        // no auth age requirement by default
+        assert_eq!(AuthRequirements::new().check(&session).is_ok(), true);
+    }
+}
@@ -1 +1,3 @@
+pub mod auth_guard;
+pub mod require_permission;
 pub mod session;
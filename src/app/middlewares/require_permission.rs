@@ -0,0 +1,116 @@
+use std::{future::Future, pin::Pin, rc::Rc};
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web, HttpMessage, ResponseError,
+};
+
+use crate::app::{
+    app_data::AppData, app_error::AppError, models::session::Session,
+    services::user::error::UserServiceError,
+};
+
+use super::auth_guard::{AuthError, AuthRequirements};
+
+/// allows the scope or resource requests only for users which roles grant the permission,
+/// anonymous session gets 401, user without the permission gets 403.
+/// User roles are taken from the user(cached) on every request, so the role change
+/// takes effect without relogin. It has to be wrapped by `SessionMiddleware`
+pub struct RequirePermission {
+    permission: &'static str,
+}
+
+impl RequirePermission {
+    pub fn new(permission: &'static str) -> Self {
+        Self { permission }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = InnerRequirePermission<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(InnerRequirePermission {
+            service: Rc::new(service),
+            permission: self.permission,
+        }))
+    }
+}
+
+pub struct InnerRequirePermission<S> {
+    service: Rc<S>,
+    permission: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for InnerRequirePermission<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let permission = self.permission;
+
+        Box::pin(async move {
+            if let Err(err) = check_permission(&req, permission).await {
+                log::debug!(
+                    "Request to {} rejected by permission {}: {}",
+                    req.path(),
+                    permission,
+                    err
+                );
+                let err_response = err.error_response();
+                let (http_req, _) = req.into_parts();
+                return Ok(ServiceResponse::new(http_req, err_response).map_into_right_body());
+            }
+            let res = service.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+async fn check_permission(req: &ServiceRequest, permission: &str) -> Result<(), AppError> {
+    let session = match req.extensions().get::<Session>() {
+        Some(session) => session.clone(),
+        None => {
+            log::error!("No Session on RequirePermission, SessionMiddleware has to wrap it");
+            return Err(AuthError::NotAuthenticated.into());
+        }
+    };
+    AuthRequirements::new().check(&session)?;
+    let app_data = match req.app_data::<web::Data<AppData>>() {
+        Some(app_data) => app_data,
+        None => {
+            log::error!("No AppData on RequirePermission to get user roles");
+            return Err(AuthError::PermissionDenied(permission.to_string()).into());
+        }
+    };
+    // the repository is cloned so the UserService lock isn't held across await
+    let mut user_repository = app_data.user_service.lock()?.get_user_repository();
+    let user = user_repository
+        .find_user_by_id(&session.user_id)
+        .await
+        .map_err(UserServiceError::from)?;
+    match user {
+        Some(user) if user.has_permission(permission) => Ok(()),
+        _ => Err(AuthError::PermissionDenied(permission.to_string()).into()),
+    }
+}
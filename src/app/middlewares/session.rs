@@ -1,36 +1,58 @@
-use std::{future::Future, pin::Pin, rc::Rc};
+use std::{future::Future, pin::Pin, rc::Rc, sync::Arc};
 
 use actix_utils::future::{ready, Ready};
 use actix_web::{
     body::{EitherBody, MessageBody},
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    HttpMessage, HttpResponse,
+    web, HttpMessage, HttpResponse, ResponseError,
 };
+use chrono::Utc;
 
 use crate::{
     app::{
+        app_data::AppData,
+        app_error::AppError,
         common::api_path::{LOGIN, LOGOUT, REGISTER},
         handlers::common::response::USER_SHOULD_RELOGIN,
-        models::session::Session,
+        models::{
+            common::AuthProviders, session::Session, session_tokens::SessionTokens, user::User,
+        },
         services::{
-            common::error_as_json, session::service::SessionService,
+            common::error_as_json,
+            session::service::SessionService,
             traits::session_storage::SessionStorage,
+            user::{
+                error::{UserServiceError, UserStatusError},
+                service::UserService,
+            },
         },
+        shared::client_info::ClientInfoResolver,
     },
-    config::session_config::SessionConfig,
+    config::session_config::{SessionAuthMode, SessionConfig},
 };
 
 #[derive(Clone)]
 pub struct SessionMiddleware<Storage: SessionStorage> {
     storage: Rc<Storage>,
     configuration: Rc<SessionConfig>,
+    // shared between workers to load GeoIP database once
+    client_info_resolver: Arc<ClientInfoResolver>,
+    // auth modes accepted on the wrapped api scope
+    auth_modes: Rc<Vec<SessionAuthMode>>,
 }
 
 impl<Storage: SessionStorage> SessionMiddleware<Storage> {
-    pub fn new(storage: Storage, configuration: SessionConfig) -> Self {
+    pub fn new(
+        storage: Storage,
+        configuration: SessionConfig,
+        client_info_resolver: Arc<ClientInfoResolver>,
+        scope: &str,
+    ) -> Self {
         Self {
             storage: Rc::new(storage),
+            auth_modes: Rc::new(configuration.get_auth_modes(scope)),
             configuration: Rc::new(configuration),
+            client_info_resolver,
         }
     }
 }
@@ -53,6 +75,8 @@ where
             service: Rc::new(service),
             configuration: Rc::clone(&self.configuration),
             storage: Rc::clone(&self.storage),
+            client_info_resolver: Arc::clone(&self.client_info_resolver),
+            auth_modes: Rc::clone(&self.auth_modes),
         }))
     }
 }
@@ -61,6 +85,8 @@ pub struct InnerSessionMiddleware<S, Storage: SessionStorage + 'static> {
     service: Rc<S>,
     configuration: Rc<SessionConfig>,
     storage: Rc<Storage>,
+    client_info_resolver: Arc<ClientInfoResolver>,
+    auth_modes: Rc<Vec<SessionAuthMode>>,
 }
 
 impl<S, B, Storage> Service<ServiceRequest> for InnerSessionMiddleware<S, Storage>
@@ -80,37 +106,102 @@ where
         let service = Rc::clone(&self.service);
         let storage = Rc::clone(&self.storage);
         let configuration = Rc::clone(&self.configuration);
+        let client_info_resolver = Arc::clone(&self.client_info_resolver);
+        let auth_modes = Rc::clone(&self.auth_modes);
 
         Box::pin(async move {
-            let session: Session;
-            let (request_ref, _) = req.parts();
-            if let Some(session_key) =
-                SessionService::get_cookie_session_id(&configuration.cookie_config, &req)
+            let request_metadata = client_info_resolver.get_session_metadata(req.request());
+            // anonymous session created on the request has to be stored after the request
+            let mut is_new_session = false;
+            let mut session =
+                match load_request_session(&req, storage.as_ref(), &configuration, &auth_modes) {
+                    Some(session) => session,
+                    None => {
+                        is_new_session = true;
+                        Session::get_anonymous_session(request_metadata.clone())
+                    }
+                };
+
+            // sessions of not active(suspended, pending deletion) user stop working immediately,
+            // it's checked before the tokens refresh to not refresh them for the user
+            if !is_new_session && !session.is_anonymous() {
+                if let Some(err) = get_user_status_error(&req, &session).await {
+                    log::debug!(
+                        "Session of not active User {} is removed: {}",
+                        &session.user_id,
+                        err
+                    );
+                    remove_session(storage.as_ref(), &session);
+                    let err_response = AppError::from(UserServiceError::from(err)).error_response();
+                    let (http_req, _) = req.into_parts();
+                    return Ok(ServiceResponse::new(http_req, err_response).map_into_right_body());
+                }
+            }
+
+            // Expired access token is refreshed on the Auth Provider while the session is alive,
+            // the session which tokens can't be refreshed is expired,
+            // on transient refresh fails(provider is down etc) the session is kept to retry on the next request
+            let mut is_session_changed = false;
+            if !is_new_session
+                && !session.is_anonymous()
+                && session.tokens.is_access_token_expired()
+                && SessionService::get_session_ttl(&configuration, &session).is_some()
             {
-                log::debug!("Session Key: {}", session_key);
-                session = match storage.as_ref().load(&session_key) {
-                    Ok(try_session) => match try_session {
-                        Some(s) => s,
-                        None => {
-                            log::debug!("No session in Cache Storage by Session Key {}. Set Anonymous Session", &session_key);
-                            Session::get_anonymous_session(Some(request_ref))
-                        }
-                    },
+                match refresh_session_tokens(&req, &session).await {
+                    Ok(Some(tokens)) => {
+                        session.tokens.update_tokens(tokens);
+                        is_session_changed = true;
+                    }
+                    Ok(None) => {
+                        log::debug!("Unable to refresh Session tokens. Set Anonymous Session");
+                        remove_session(storage.as_ref(), &session);
+                        session = Session::get_anonymous_session(request_metadata.clone());
+                        is_new_session = true;
+                    }
                     Err(err) => {
-                        log::error!(
-                            "Unable to get user session from cache storage by Session Key: {}, error: {:?}",
-                            &session_key,
-                            err
-                        );
-                        // it didn't get Session from CacheStorage by Session Key
-                        // set an anonymous Session
-                        Session::get_anonymous_session(Some(request_ref))
+                        log::error!("Error to refresh Session tokens: {:?}", err);
                     }
                 }
-            } else {
-                log::debug!("No Session Key on request. Set Anonymous Session");
-                session = Session::get_anonymous_session(Some(request_ref));
-            };
+            }
+
+            // last seen client ip is kept up to date, first seen one stays from the session creation
+            if !is_new_session && session.metadata.update_client_ip(&request_metadata) {
+                is_session_changed = true;
+            }
+
+            // Sliding expiration: stored session ttl is extended on user activity
+            // but never over the absolute session lifetime and provider tokens expiration.
+            // It's done before the request to not restore the session removed by the request(revoke, logout)
+            if !is_new_session {
+                match SessionService::get_session_ttl(&configuration, &session) {
+                    None => {
+                        log::debug!("Session is expired. Set Anonymous Session");
+                        remove_session(storage.as_ref(), &session);
+                        session = Session::get_anonymous_session(request_metadata.clone());
+                        is_new_session = true;
+                    }
+                    Some(_)
+                        if is_session_changed
+                            || SessionService::is_activity_update_required(
+                                &configuration,
+                                &session,
+                            ) =>
+                    {
+                        session.updated_at = Utc::now();
+                        if let Some(session_ttl) =
+                            SessionService::get_session_ttl(&configuration, &session)
+                        {
+                            if let Err(err) = storage.as_ref().set(&session, session_ttl) {
+                                log::error!(
+                                    "Error to update session activity on Session Middleware: {:?}",
+                                    err
+                                );
+                            }
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
 
             // To do not create a new not anonymous session when request has it on LOGIN or REGISTER endpoints
             if !session.is_anonymous()
@@ -125,6 +216,14 @@ where
 
             req.extensions_mut().insert(session.clone());
 
+            // the limit is checked before the handler call, so the handler requiring the session
+            // persistence is rejected(429) before it stores any data of the session
+            if is_new_session
+                && !is_anonymous_session_allowed(storage.as_ref(), &configuration, &session)
+            {
+                Session::set_anonymous_session_limit_reached(req.request());
+            }
+
             // Need to exclude only once the case when user /logout
             let is_restricted_endpoint_call =
                 req.path().contains(LOGOUT) && !session.is_anonymous();
@@ -133,15 +232,22 @@ where
             //
             // Everything after the call
             //
-            let m_res = res.response_mut();
-            let session_cookie = m_res
+            let is_session_cookie_set = res
+                .response()
                 .cookies()
-                .into_iter()
-                .find(|cookie| cookie.name() == &configuration.cookie_config.name);
-            if session_cookie.is_none() && !is_restricted_endpoint_call {
+                .any(|cookie| cookie.name() == &configuration.cookie_config.name);
+            // anonymous session is stored only if the handler requires it,
+            // requests without session(health checks, bots) don't get it
+            if !is_session_cookie_set
+                && !is_restricted_endpoint_call
+                && is_new_session
+                && Session::is_persistence_required(res.request())
+            {
+                count_anonymous_session(storage.as_ref(), &configuration, &session);
+                let m_res = res.response_mut();
                 if let Err(err) = storage
                     .as_ref()
-                    .set(&session, configuration.session_ttl_sec)
+                    .set(&session, configuration.anonymous_session_ttl_sec)
                 {
                     log::error!(
                         "Error to set session in Session Storage on Session Middleware: {:?}",
@@ -160,3 +266,264 @@ where
         })
     }
 }
+
+/// returns the stored session of the request by the scope accepted auth modes,
+/// Bearer token takes precedence over the session cookie
+fn load_request_session<Storage: SessionStorage>(
+    req: &ServiceRequest,
+    storage: &Storage,
+    configuration: &SessionConfig,
+    auth_modes: &[SessionAuthMode],
+) -> Option<Session> {
+    if let Some(bearer_token) = SessionService::get_bearer_token(req) {
+        if SessionService::is_jwt(&bearer_token) {
+            if auth_modes.contains(&SessionAuthMode::BearerJwt) {
+                return load_jwt_session(req, storage, &bearer_token);
+            }
+        } else if auth_modes.contains(&SessionAuthMode::BearerSession) {
+            return load_session(storage, &bearer_token);
+        }
+        log::debug!("Bearer token auth mode is not accepted on the scope, ignoring...");
+    }
+    if !auth_modes.contains(&SessionAuthMode::Cookie) {
+        log::debug!("No accepted Session Key on request. Set Anonymous Session");
+        return None;
+    }
+    match SessionService::get_cookie_session_id(&configuration.cookie_config, req) {
+        Some(session_key) => load_session(storage, &session_key),
+        None => {
+            log::debug!("No Session Key on request. Set Anonymous Session");
+            None
+        }
+    }
+}
+
+fn load_session<Storage: SessionStorage>(storage: &Storage, session_key: &str) -> Option<Session> {
+    log::debug!("Session Key: {}", session_key);
+    match storage.load(session_key) {
+        Ok(Some(session)) => Some(session),
+        Ok(None) => {
+            log::debug!(
+                "No session in Cache Storage by Session Key {}. Set Anonymous Session",
+                session_key
+            );
+            None
+        }
+        Err(err) => {
+            // it didn't get Session from CacheStorage by Session Key
+            // set an anonymous Session
+            log::error!(
+                "Unable to get user session from cache storage by Session Key: {}, error: {:?}",
+                session_key,
+                err
+            );
+            None
+        }
+    }
+}
+
+/// CyberSherlock access JWT is accepted only while the session it's issued for is alive,
+/// so logout and session revoke invalidate the token too
+fn load_jwt_session<Storage: SessionStorage>(
+    req: &ServiceRequest,
+    storage: &Storage,
+    token: &str,
+) -> Option<Session> {
+    let app_data = match req.app_data::<web::Data<AppData>>() {
+        Some(app_data) => app_data,
+        None => {
+            log::error!("No AppData on Session Middleware to verify access token");
+            return None;
+        }
+    };
+    let claims = match app_data.cyber_sherlock_auth_provider.lock() {
+        Ok(provider) => provider.verify_access_token(token),
+        Err(err) => {
+            log::error!("Unable to lock CyberSherlockAuthProvider: {}", err);
+            return None;
+        }
+    };
+    let claims = match claims {
+        Ok(claims) => claims,
+        Err(err) => {
+            log::debug!(
+                "Invalid Bearer access token: {:?}. Set Anonymous Session",
+                err
+            );
+            return None;
+        }
+    };
+    let sessions = match storage.load_user_sessions(&claims.sub) {
+        Ok(sessions) => sessions,
+        Err(err) => {
+            log::error!(
+                "Unable to get user {} sessions from cache storage, error: {:?}",
+                &claims.sub,
+                err
+            );
+            return None;
+        }
+    };
+    let session = sessions.into_iter().find(|session| {
+        session
+            .auth_provider
+            .is_equal(&AuthProviders::CyberSherlock)
+            && session
+                .tokens
+                .access_token
+                .as_ref()
+                .is_some_and(|access_token| access_token.token_string == token)
+    });
+    if session.is_none() {
+        log::debug!("No session of the Bearer access token. Set Anonymous Session");
+    }
+    session
+}
+
+/// anonymous sessions number from one client ip is limited to not flood the session storage,
+/// the session storage fails don't block the client
+fn is_anonymous_session_allowed<Storage: SessionStorage>(
+    storage: &Storage,
+    configuration: &SessionConfig,
+    session: &Session,
+) -> bool {
+    if configuration.anonymous_sessions_per_ip == 0 {
+        return true;
+    }
+    let client_ip = match &session.metadata.last_seen_ip {
+        Some(client_ip) => client_ip,
+        None => return true,
+    };
+    match storage.get_anonymous_sessions_count(client_ip) {
+        Ok(sessions) if sessions >= configuration.anonymous_sessions_per_ip => {
+            log::warn!(
+                "Anonymous sessions limit is reached for client ip {}",
+                client_ip
+            );
+            false
+        }
+        Ok(_) => true,
+        Err(err) => {
+            log::error!(
+                "Error to get anonymous sessions count of {}: {:?}",
+                client_ip,
+                err
+            );
+            true
+        }
+    }
+}
+
+/// counts the stored anonymous session to the client ip limit
+fn count_anonymous_session<Storage: SessionStorage>(
+    storage: &Storage,
+    configuration: &SessionConfig,
+    session: &Session,
+) {
+    if configuration.anonymous_sessions_per_ip == 0 {
+        return;
+    }
+    if let Some(client_ip) = &session.metadata.last_seen_ip {
+        if let Err(err) =
+            storage.count_anonymous_session(client_ip, configuration.anonymous_session_ttl_sec)
+        {
+            log::error!(
+                "Error to count anonymous session of {}: {:?}",
+                client_ip,
+                err
+            );
+        }
+    }
+}
+
+fn remove_session<Storage: SessionStorage>(storage: &Storage, session: &Session) {
+    if let Err(err) = storage.remove(session) {
+        log::error!(
+            "Error to remove session from Session Storage on Session Middleware: {:?}",
+            err
+        );
+    }
+}
+
+/// returns the status error of the session user, user status check fails are logged only,
+/// the request handlers fail on the same services anyway
+async fn get_user_status_error(req: &ServiceRequest, session: &Session) -> Option<UserStatusError> {
+    let app_data = match req.app_data::<web::Data<AppData>>() {
+        Some(app_data) => app_data,
+        None => {
+            log::error!("No AppData on Session Middleware to check User status");
+            return None;
+        }
+    };
+    // the repository is cloned so the UserService lock isn't held across await
+    let mut user_repository = match app_data.user_service.lock() {
+        Ok(user_service) => user_service.get_user_repository(),
+        Err(err) => {
+            log::error!("Unable to lock UserService to check User status: {}", err);
+            return None;
+        }
+    };
+    match user_repository.find_user_by_id(&session.user_id).await {
+        Ok(Some(user)) => UserService::check_user_status(&user).err(),
+        Ok(None) => None,
+        Err(err) => {
+            log::error!(
+                "Unable to get User {} to check status: {}",
+                &session.user_id,
+                err
+            );
+            None
+        }
+    }
+}
+
+/// returns new tokens issued by the session Auth Provider,
+/// None means the session tokens can't be refreshed anymore, errors are transient fails
+async fn refresh_session_tokens(
+    req: &ServiceRequest,
+    session: &Session,
+) -> Result<Option<SessionTokens>, AppError> {
+    let app_data = match req.app_data::<web::Data<AppData>>() {
+        Some(app_data) => app_data,
+        None => {
+            log::error!("No AppData on Session Middleware to refresh Session tokens");
+            return Ok(None);
+        }
+    };
+    match session.auth_provider {
+        AuthProviders::Google => {
+            let refresh_token = match &session.tokens.refresh_token {
+                Some(token) => token.token_string.clone(),
+                None => return Ok(None),
+            };
+            // the lock is released before the refresh request is awaited
+            let refresh = app_data
+                .google_provider
+                .lock()?
+                .refresh_tokens(&refresh_token)?;
+            Ok(refresh.await?)
+        }
+        AuthProviders::CyberSherlock => {
+            let mut user_repository = app_data.user_service.lock()?.get_user_repository();
+            let user = user_repository
+                .find_user_by_id(&session.user_id)
+                .await
+                .map_err(UserServiceError::from)?;
+            let (profile, roles) = match user {
+                Some(User {
+                    cyber_sherlock: Some(profile),
+                    roles,
+                    ..
+                }) => (profile, roles),
+                _ => return Ok(None),
+            };
+            let cyber_sherlock_auth_provider = app_data.cyber_sherlock_auth_provider.lock()?;
+            let tokens = cyber_sherlock_auth_provider.refresh_tokens(&profile, &roles)?;
+            Ok(Some(tokens))
+        }
+        // Facebook has no refresh token, the user has to login again
+        AuthProviders::Facebook => Ok(None),
+    }
+}
+
+mod test;
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use bson::oid::ObjectId;
+
+    use crate::{
+        app::{
+            models::{session::Session, session_metadata::SessionMetadata},
+            services::{
+                cache::{common::CacheServiceType, service::RedisCacheService},
+                traits::session_storage::SessionStorage,
+            },
+        },
+        config::session_config::SessionConfig,
+    };
+
+    use super::super::{count_anonymous_session, is_anonymous_session_allowed};
+
+    #[test]
+    fn anonymous_sessions_are_limited_per_client_ip() {
+        let (storage, mut configuration) = initialize();
+        configuration.anonymous_sessions_per_ip = 2;
+        let session = get_anonymous_session(Some(format!("test-{}", ObjectId::new())));
+
+        // stored sessions are counted, the limit is reached on the limit number of sessions
+        for _ in 0..2 {
+            assert_eq!(
+                is_anonymous_session_allowed(&storage, &configuration, &session),
+                true
+            );
+            count_anonymous_session(&storage, &configuration, &session);
+        }
+        assert_eq!(
+            is_anonymous_session_allowed(&storage, &configuration, &session),
+            false
+        );
+
+        // the check doesn't count the session itself
+        if let Some(client_ip) = &session.metadata.last_seen_ip {
+            assert_eq!(
+                storage.get_anonymous_sessions_count(client_ip).ok(),
+                Some(2)
+            );
+        }
+
+        // other client ip has own limit
+        let other_session = get_anonymous_session(Some(format!("test-{}", ObjectId::new())));
+        assert_eq!(
+            is_anonymous_session_allowed(&storage, &configuration, &other_session),
+            true
+        );
+    }
+
+    #[test]
+    fn anonymous_sessions_without_limit() {
+        let (storage, mut configuration) = initialize();
+
+        // 0 means no limit
+        configuration.anonymous_sessions_per_ip = 0;
+        let session = get_anonymous_session(Some(format!("test-{}", ObjectId::new())));
+        count_anonymous_session(&storage, &configuration, &session);
+        assert_eq!(
+            is_anonymous_session_allowed(&storage, &configuration, &session),
+            true
+        );
+        if let Some(client_ip) = &session.metadata.last_seen_ip {
+            assert_eq!(
+                storage.get_anonymous_sessions_count(client_ip).ok(),
+                Some(0)
+            );
+        }
+
+        // unknown client ip is not limited
+        configuration.anonymous_sessions_per_ip = 1;
+        let session = get_anonymous_session(None);
+        count_anonymous_session(&storage, &configuration, &session);
+        assert_eq!(
+            is_anonymous_session_allowed(&storage, &configuration, &session),
+            true
+        );
+    }
+
+    fn get_anonymous_session(client_ip: Option<String>) -> Session {
+        let mut session_metadata = SessionMetadata::new();
+        session_metadata.last_seen_ip = client_ip;
+        Session::get_anonymous_session(session_metadata)
+    }
+
+    fn initialize() -> (RedisCacheService, SessionConfig) {
+        let storage = RedisCacheService::new(CacheServiceType::Session)
+            .expect("Error to create Session Cache");
+        (storage, SessionConfig::new())
+    }
+}
@@ -1,5 +1,6 @@
 pub mod app_data;
 pub mod app_error;
+pub mod cli;
 pub mod common;
 pub mod handlers;
 pub mod middlewares;
@@ -9,28 +10,59 @@ pub mod repositories;
 pub mod services;
 pub mod shared;
 
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use actix_web_validator::JsonConfig;
 
 use crate::app::app_data::AppData;
 use crate::app::app_error::error_handler;
 use crate::app::common::api_path::{
-    API, AUTH, CALLBACK, CYBER_SHERLOCK, FACEBOOK, GOOGLE, LOGIN, LOGOUT, ME, REGISTER, STATUS,
-    USER, V1,
+    ADMIN, ADMIN_USER, ADMIN_USER_PASSWORD_RESET, ADMIN_USER_SESSIONS, ADMIN_USER_SUSPENSION, API,
+    AUTH, CALLBACK, CYBER_SHERLOCK, FACEBOOK, GOOGLE, LOGIN, LOGOUT, ME, METRICS,
+    ME_CONTACT_CONFIRM, ME_EMAIL, ME_EXPORT, ME_PHONE, PASSWORD_RESET, REGISTER, STATUS, USER,
+    USERS, USERS_MERGE, USER_ACTIVE_PROFILE, USER_EXPORT, USER_MAX_SESSIONS, USER_MERGE_CONFIRM,
+    USER_OTHER_SESSIONS, USER_PROVIDER, USER_ROLES, USER_SESSION, USER_SESSIONS, V1,
+};
+use crate::app::handlers::admin::{
+    merge_users::merge_users,
+    user_roles::{set_user_max_sessions, set_user_roles},
+    users::{
+        force_logout, get_user, reactivate_user, search_users, suspend_user, trigger_password_reset,
+    },
 };
 use crate::app::handlers::logout::logout;
 use crate::app::handlers::me::me;
+use crate::app::handlers::user::{
+    active_profile::set_active_profile,
+    change_contact::{change_email, change_phone, confirm_contact_change},
+    confirm_merge::confirm_merge,
+    delete_me::delete_me,
+    export::{download_export, export_me},
+    sessions::{get_sessions, logout_everywhere, revoke_other_sessions, revoke_session},
+    unlink_provider::unlink_provider,
+    update_me::update_me,
+};
+use crate::app::middlewares::auth_guard::{AuthGuard, AuthRequirements};
+use crate::app::middlewares::require_permission::RequirePermission;
 use crate::app::middlewares::session::SessionMiddleware;
+use crate::app::models::role::permission;
 use crate::app::services::cache::common::CacheServiceType;
 use crate::app::services::cache::service::RedisCacheService;
+use crate::app::services::session::service::SessionService;
+use crate::app::services::user::service::UserService;
+use crate::app::shared::client_info::ClientInfoResolver;
 use crate::config::app_config::AppConfig;
 use crate::config::session_config::SessionConfig;
+use crate::config::user_config::UserConfig;
 use env_logger::Env;
 use log::info;
 
 use crate::app::handlers::cyber_sherlock::{
     auth_callback::auth_callback as cyber_sherlock_auth_callback,
     login::login as login_with_cyber_sherlock, register::register as cyber_sherlock_register,
+    reset_password::reset_password as cyber_sherlock_reset_password,
 };
 use crate::app::handlers::facebook::{
     auth_callback::auth_callback as facebook_auth_callback, login::login as login_with_facebook,
@@ -39,7 +71,7 @@ use crate::app::handlers::google::{
     auth_callback::auth_callback as google_auth_callback, login::login as login_with_google,
 };
 
-use crate::app::handlers::health_check::status;
+use crate::app::handlers::health_check::{metrics, status};
 
 /**
  * TODO:
@@ -65,6 +97,22 @@ pub async fn run() -> std::io::Result<()> {
     let session_cache_service = RedisCacheService::new(CacheServiceType::Session)
         .expect("Unable to create CacheService for Session Middleware");
 
+    let user_config = UserConfig::new();
+
+    spawn_deleted_users_purge(
+        app_data.user_service.clone(),
+        user_config.deletion_purge_interval_sec,
+    );
+
+    let session_config = SessionConfig::new();
+
+    let client_info_resolver = Arc::new(ClientInfoResolver::new(&session_config));
+
+    spawn_sessions_index_cleanup(
+        SessionService::new(session_config.clone(), session_cache_service.clone()),
+        session_config.index_cleanup_interval_sec,
+    );
+
     HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
@@ -73,12 +121,14 @@ pub async fn run() -> std::io::Result<()> {
             .service(
                 web::scope(API).service(
                     web::scope(V1)
-                        .wrap(SessionMiddleware::new(
-                            session_cache_service.clone(),
-                            SessionConfig::new(),
-                        ))
                         .service(
                             web::scope(AUTH)
+                                .wrap(SessionMiddleware::new(
+                                    session_cache_service.clone(),
+                                    session_config.clone(),
+                                    client_info_resolver.clone(),
+                                    AUTH,
+                                ))
                                 .service(
                                     web::scope(CYBER_SHERLOCK)
                                         .route(LOGIN, web::post().to(login_with_cyber_sherlock))
@@ -86,7 +136,11 @@ pub async fn run() -> std::io::Result<()> {
                                             CALLBACK,
                                             web::get().to(cyber_sherlock_auth_callback),
                                         )
-                                        .route(REGISTER, web::post().to(cyber_sherlock_register)),
+                                        .route(REGISTER, web::post().to(cyber_sherlock_register))
+                                        .route(
+                                            PASSWORD_RESET,
+                                            web::post().to(cyber_sherlock_reset_password),
+                                        ),
                                 )
                                 .service(
                                     web::scope(FACEBOOK)
@@ -100,12 +154,137 @@ pub async fn run() -> std::io::Result<()> {
                                 )
                                 .route(LOGOUT, web::get().to(logout)),
                         )
-                        .service(web::scope(USER).route(ME, web::get().to(me))),
+                        .service(
+                            web::scope(USER)
+                                .wrap(SessionMiddleware::new(
+                                    session_cache_service.clone(),
+                                    session_config.clone(),
+                                    client_info_resolver.clone(),
+                                    USER,
+                                ))
+                                // export link is sent by email, it's downloaded without session
+                                .route(USER_EXPORT, web::get().to(download_export))
+                                .service(
+                                    web::scope("")
+                                        .wrap(AuthGuard::new(AuthRequirements::new()))
+                                        .route(ME, web::get().to(me))
+                                        .route(ME, web::patch().to(update_me))
+                                        .service(
+                                            // account deletion requires recent login
+                                            web::resource(ME)
+                                                .app_data(AuthRequirements::new().max_auth_age(
+                                                    user_config.reauthentication_max_age_sec,
+                                                ))
+                                                .route(web::delete().to(delete_me)),
+                                        )
+                                        .route(ME_EXPORT, web::get().to(export_me))
+                                        .route(ME_EMAIL, web::post().to(change_email))
+                                        .route(ME_PHONE, web::post().to(change_phone))
+                                        .route(
+                                            ME_CONTACT_CONFIRM,
+                                            web::post().to(confirm_contact_change),
+                                        )
+                                        .route(USER_SESSIONS, web::get().to(get_sessions))
+                                        .route(USER_SESSIONS, web::delete().to(logout_everywhere))
+                                        .route(
+                                            USER_OTHER_SESSIONS,
+                                            web::delete().to(revoke_other_sessions),
+                                        )
+                                        .route(USER_SESSION, web::delete().to(revoke_session))
+                                        .route(USER_PROVIDER, web::delete().to(unlink_provider))
+                                        .route(
+                                            USER_ACTIVE_PROFILE,
+                                            web::put().to(set_active_profile),
+                                        )
+                                        .route(USER_MERGE_CONFIRM, web::post().to(confirm_merge)),
+                                ),
+                        )
+                        .service(
+                            web::scope(ADMIN)
+                                // users:read is required for the whole scope,
+                                // write operations require their own permissions in addition
+                                .wrap(RequirePermission::new(permission::USERS_READ))
+                                .wrap(SessionMiddleware::new(
+                                    session_cache_service.clone(),
+                                    session_config.clone(),
+                                    client_info_resolver.clone(),
+                                    ADMIN,
+                                ))
+                                .route(USERS, web::get().to(search_users))
+                                .service(
+                                    web::resource(USERS_MERGE)
+                                        .wrap(RequirePermission::new(permission::USERS_MERGE))
+                                        .route(web::post().to(merge_users)),
+                                )
+                                .route(ADMIN_USER, web::get().to(get_user))
+                                .service(
+                                    web::resource(ADMIN_USER_SUSPENSION)
+                                        .wrap(RequirePermission::new(permission::USERS_WRITE))
+                                        .route(web::put().to(suspend_user))
+                                        .route(web::delete().to(reactivate_user)),
+                                )
+                                .service(
+                                    web::resource(ADMIN_USER_SESSIONS)
+                                        .wrap(RequirePermission::new(permission::SESSIONS_REVOKE))
+                                        .route(web::delete().to(force_logout)),
+                                )
+                                .service(
+                                    web::resource(ADMIN_USER_PASSWORD_RESET)
+                                        .wrap(RequirePermission::new(permission::USERS_WRITE))
+                                        .route(web::post().to(trigger_password_reset)),
+                                )
+                                .service(
+                                    web::resource(USER_ROLES)
+                                        .wrap(RequirePermission::new(permission::ROLES_ASSIGN))
+                                        .route(web::put().to(set_user_roles)),
+                                )
+                                .service(
+                                    web::resource(USER_MAX_SESSIONS)
+                                        .wrap(RequirePermission::new(permission::ROLES_ASSIGN))
+                                        .route(web::put().to(set_user_max_sessions)),
+                                ),
+                        ),
                 ),
             )
             .route(STATUS, web::get().to(status))
+            .route(METRICS, web::get().to(metrics))
     })
     .bind(server_address_with_port)?
     .run()
     .await
 }
+
+/// purges soft deleted users with expired grace period in background
+fn spawn_deleted_users_purge(user_service: Arc<Mutex<UserService>>, interval_sec: u64) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(interval_sec));
+        loop {
+            interval.tick().await;
+            let mut user_service = match user_service.lock() {
+                Ok(service) => service,
+                Err(err) => {
+                    log::error!("Unable to lock UserService to purge users: {}", err);
+                    continue;
+                }
+            };
+            match user_service.purge_deleted_users().await {
+                Ok(report) if report.purged == 0 && report.failed == 0 => {}
+                Ok(report) => info!("Deleted users purge: {:?}", report),
+                Err(err) => log::error!("Unable to purge deleted users: {}", err),
+            }
+        }
+    });
+}
+
+fn spawn_sessions_index_cleanup(mut session_service: SessionService, interval_sec: u64) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(interval_sec));
+        loop {
+            interval.tick().await;
+            match session_service.cleanup_sessions_indexes().await {
+                Ok(report) => info!("Sessions indexes cleanup: {:?}", report),
+                Err(err) => log::error!("Unable to cleanup sessions indexes: {}", err),
+            }
+        }
+    });
+}
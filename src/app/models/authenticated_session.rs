@@ -0,0 +1,46 @@
+use std::ops::Deref;
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+
+use crate::app::{app_error::AppError, middlewares::auth_guard::AuthRequirements};
+
+use super::session::Session;
+
+/// not anonymous session satisfying the scope or resource `AuthRequirements` app data,
+/// the request is rejected with 401 otherwise
+#[derive(Debug, Clone)]
+pub struct AuthenticatedSession(Session);
+
+impl AuthenticatedSession {
+    pub fn into_inner(self) -> Session {
+        self.0
+    }
+}
+
+impl Deref for AuthenticatedSession {
+    type Target = Session;
+
+    fn deref(&self) -> &Session {
+        &self.0
+    }
+}
+
+impl FromRequest for AuthenticatedSession {
+    type Error = AppError;
+    type Future = Ready<Result<AuthenticatedSession, AppError>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let session = Session::get_session_from_http_request(req);
+        let check_result = match req.app_data::<AuthRequirements>() {
+            Some(requirements) => requirements.check(&session),
+            None => AuthRequirements::default().check(&session),
+        };
+        ready(
+            check_result
+                .map(|_| AuthenticatedSession(session))
+                .map_err(AppError::from),
+        )
+    }
+}
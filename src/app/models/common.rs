@@ -25,7 +25,7 @@ impl FromStr for AuthProviders {
         match s.to_lowercase().as_str() {
             "google" => Ok(AuthProviders::Google),
             "facebook" => Ok(AuthProviders::Facebook),
-            "cybersherlock" => Ok(AuthProviders::CyberSherlock),
+            "cybersherlock" | "cyber_sherlock" => Ok(AuthProviders::CyberSherlock),
             _ => Err("Invalid AuthProvider"),
         }
     }
@@ -93,3 +93,29 @@ pub mod datetime_as_mongo_bson {
         }
     }
 }
+
+pub mod optional_datetime_as_mongo_bson {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(val: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match val {
+            Some(datetime) => super::datetime_as_mongo_bson::serialize(datetime, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::datetime_as_mongo_bson")] DateTime<Utc>);
+
+        let value: Option<Wrapper> = Deserialize::deserialize(deserializer)?;
+        Ok(value.map(|Wrapper(datetime)| datetime))
+    }
+}
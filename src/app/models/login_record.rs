@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    common::{datetime_as_mongo_bson, AuthProviders},
+    session::Session,
+    session_metadata::{DeviceInfo, Location},
+};
+
+// number of the latest logins kept in the user login history
+pub const LOGIN_HISTORY_SIZE: i32 = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LoginRecord {
+    pub auth_provider: AuthProviders,
+    pub user_agent: String,
+    #[serde(default)]
+    pub device: DeviceInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    #[serde(with = "datetime_as_mongo_bson")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl LoginRecord {
+    pub fn new(session: &Session) -> Self {
+        LoginRecord {
+            auth_provider: session.auth_provider,
+            user_agent: session.metadata.user_agent.clone(),
+            device: session.metadata.device.clone(),
+            ip: session.metadata.first_seen_ip.clone(),
+            location: session.metadata.location.clone(),
+            created_at: session.created_at,
+        }
+    }
+}
@@ -1,7 +1,16 @@
+pub mod authenticated_session;
 pub mod common;
+pub mod login_record;
+pub mod pending_merge;
+pub mod role;
 pub mod session;
 pub mod session_metadata;
 pub mod session_tokens;
 pub mod token;
 pub mod user;
+pub mod user_audit;
+pub mod user_data_export;
+pub mod user_event;
+pub mod user_merge_report;
 pub mod user_profile;
+pub mod user_status;
@@ -0,0 +1,37 @@
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value as RedisValue};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{user::UserId, user_profile::UserProfile};
+
+/// Provider profile which matches an existing user by email but can't be merged
+/// automatically, it waits until the existing user confirms the merge
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PendingMerge {
+    pub user_id: UserId,
+    pub profile: UserProfile,
+}
+
+impl PendingMerge {
+    pub fn generate_merge_token() -> String {
+        Uuid::new_v4().to_string()
+    }
+
+    /// the key is scoped by the user, so the token of other user can't be taken
+    pub fn get_pending_merge_key(user_id: &UserId, merge_token: &str) -> String {
+        format!("user::merges::{}::{}", user_id, merge_token)
+    }
+}
+
+impl FromRedisValue for PendingMerge {
+    fn from_redis_value(value: &RedisValue) -> RedisResult<PendingMerge> {
+        match *value {
+            RedisValue::Data(ref data) => Ok(serde_json::from_slice::<PendingMerge>(data)?),
+            _ => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "Response was of incompatible type",
+                format!("(response was {:?})", value),
+            ))),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+pub mod permission {
+    pub const USERS_READ: &str = "users:read";
+    pub const USERS_WRITE: &str = "users:write";
+    pub const USERS_MERGE: &str = "users:merge";
+    pub const ROLES_ASSIGN: &str = "roles:assign";
+    pub const SESSIONS_REVOKE: &str = "sessions:revoke";
+}
+
+/// user roles, every role grants a fixed set of permissions,
+/// user without roles is a regular user with access to own data only
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    // read-only access to users for customer support
+    Support,
+    // users management
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn get_permissions(&self) -> &'static [&'static str] {
+        match self {
+            Role::Support => &[permission::USERS_READ],
+            Role::Moderator => &[
+                permission::USERS_READ,
+                permission::USERS_WRITE,
+                permission::SESSIONS_REVOKE,
+            ],
+            Role::Admin => &[
+                permission::USERS_READ,
+                permission::USERS_WRITE,
+                permission::USERS_MERGE,
+                permission::ROLES_ASSIGN,
+                permission::SESSIONS_REVOKE,
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let role = match self {
+            Role::Support => "support",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        };
+        write!(f, "{}", role)
+    }
+}
+
+impl FromStr for Role {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "support" => Ok(Role::Support),
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            _ => Err("Invalid Role"),
+        }
+    }
+}
+
+/// returns sorted permissions granted by the roles without duplicates
+pub fn get_roles_permissions(roles: &[Role]) -> Vec<String> {
+    let mut permissions: Vec<String> = roles
+        .iter()
+        .flat_map(|role| role.get_permissions().iter())
+        .map(|permission| permission.to_string())
+        .collect();
+    permissions.sort();
+    permissions.dedup();
+    permissions
+}
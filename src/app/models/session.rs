@@ -1,5 +1,6 @@
 use actix_utils::future::{ready, Ready};
 use actix_web::{dev::Payload, error::Error, FromRequest, HttpMessage, HttpRequest};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use bson::oid::ObjectId;
 use chrono::{DateTime, Utc};
 use redis::{
@@ -7,11 +8,14 @@ use redis::{
     Value as RedisValue,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use std::convert::TryInto;
 
 use rand::{distributions::Alphanumeric, rngs::OsRng, Rng as _};
 
+use crate::app::app_error::AppError;
+
 use super::{
     common::AuthProviders, session_metadata::SessionMetadata, session_tokens::SessionTokens,
 };
@@ -25,6 +29,48 @@ pub struct NewSessionData {
     pub session_metadata: SessionMetadata,
 }
 
+// public session id length, it's a part of session id hash
+const PUBLIC_SESSION_ID_LENGTH: usize = 16;
+
+// header the clients without cookies(mobile, CLI) set to get the session token in response body,
+// provider callbacks are browser redirects, so it's set on login(register) request and kept till the callback
+pub const SESSION_DELIVERY_HEADER: &str = "X-Session-Delivery";
+const SESSION_DELIVERY_TOKEN: &str = "token";
+
+/// how the new session is delivered to the client on login
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionDelivery {
+    // private session cookie
+    #[default]
+    Cookie,
+    // session token in response body to be sent in `Authorization: Bearer` header
+    Token,
+}
+
+impl SessionDelivery {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        match req
+            .headers()
+            .get(SESSION_DELIVERY_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            Some(delivery) if delivery.eq_ignore_ascii_case(SESSION_DELIVERY_TOKEN) => {
+                SessionDelivery::Token
+            }
+            _ => SessionDelivery::Cookie,
+        }
+    }
+}
+
+// request extension to mark the anonymous session has to be stored after the request
+#[derive(Clone, Copy)]
+struct PersistentSessionRequired;
+
+// request extension to mark the client can't store one more anonymous session
+#[derive(Clone, Copy)]
+struct AnonymousSessionLimitReached;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Session {
     pub anonimous: bool,
@@ -35,6 +81,9 @@ pub struct Session {
     pub metadata: SessionMetadata,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    // set by MFA step-up, None means the session has passed the first factor only
+    #[serde(default)]
+    pub mfa_completed_at: Option<DateTime<Utc>>,
 }
 
 impl Session {
@@ -49,6 +98,7 @@ impl Session {
             metadata: session_data.session_metadata,
             created_at: now,
             updated_at: now,
+            mfa_completed_at: None,
         }
     }
 
@@ -56,6 +106,13 @@ impl Session {
         self.anonimous
     }
 
+    /// returns the session reference safe to show to the user,
+    /// session id itself is a secret and never leaves the session cookie
+    pub fn get_public_id(&self) -> String {
+        let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(self.id.as_bytes()));
+        hash[..PUBLIC_SESSION_ID_LENGTH].to_string()
+    }
+
     pub fn generate_session_id() -> String {
         // Session key generation routine that follows [OWASP recommendations].
         //
@@ -78,11 +135,31 @@ impl Session {
         format!("user::sessions::{}", user_id)
     }
 
-    pub fn get_anonymous_session(req: Option<&HttpRequest>) -> Session {
-        let mut session_metadata = SessionMetadata::new();
-        if let Some(request) = req {
-            session_metadata.set_metadata_from_request(request);
+    pub fn get_anonymous_sessions_counter_key(client_ip: &str) -> String {
+        format!("anonymous_sessions::{}", client_ip)
+    }
+
+    /// anonymous session is stored only in case the handler needs it on the next requests
+    /// (e.g. login flow keeps it in login cache data), it has to be called before the handler
+    /// stores any data of the session, the error means the client anonymous sessions limit is reached
+    pub fn require_persistence(req: &HttpRequest) -> Result<(), AppError> {
+        if req.extensions().contains::<AnonymousSessionLimitReached>() {
+            return Err(AppError::AnonymousSessionLimitError);
         }
+        req.extensions_mut().insert(PersistentSessionRequired);
+        Ok(())
+    }
+
+    /// it's checked by the session middleware before the handler call
+    pub fn set_anonymous_session_limit_reached(req: &HttpRequest) {
+        req.extensions_mut().insert(AnonymousSessionLimitReached);
+    }
+
+    pub fn is_persistence_required(req: &HttpRequest) -> bool {
+        req.extensions().contains::<PersistentSessionRequired>()
+    }
+
+    pub fn get_anonymous_session(session_metadata: SessionMetadata) -> Session {
         Session::new(NewSessionData {
             anonimous: true,
             auth_provider: AuthProviders::CyberSherlock,
@@ -96,7 +173,31 @@ impl Session {
         if let Some(session) = req.extensions_mut().get::<Session>() {
             session.to_owned()
         } else {
-            Session::get_anonymous_session(None)
+            Session::get_anonymous_session(SessionMetadata::new())
+        }
+    }
+}
+
+/// Session data shown to the user in active sessions list
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionInfo {
+    pub id: String,
+    pub auth_provider: AuthProviders,
+    pub metadata: SessionMetadata,
+    pub created_at: DateTime<Utc>,
+    pub last_activity_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+impl SessionInfo {
+    pub fn new(session: Session, current_session_id: &str) -> Self {
+        SessionInfo {
+            id: session.get_public_id(),
+            current: session.id == current_session_id,
+            auth_provider: session.auth_provider,
+            metadata: session.metadata,
+            created_at: session.created_at,
+            last_activity_at: session.updated_at,
         }
     }
 }
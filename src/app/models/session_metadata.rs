@@ -3,10 +3,63 @@ use serde::{Deserialize, Serialize};
 
 const UNKNOWN_DATA: &str = "unknown";
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeviceInfo {
+    pub browser: String,
+    pub browser_version: String,
+    pub os: String,
+    pub os_version: String,
+    // pc, smartphone, mobilephone, crawler, appliance, misc
+    pub device_type: String,
+}
+
+impl Default for DeviceInfo {
+    fn default() -> Self {
+        DeviceInfo {
+            browser: UNKNOWN_DATA.to_string(),
+            browser_version: UNKNOWN_DATA.to_string(),
+            os: UNKNOWN_DATA.to_string(),
+            os_version: UNKNOWN_DATA.to_string(),
+            device_type: UNKNOWN_DATA.to_string(),
+        }
+    }
+}
+
+impl DeviceInfo {
+    pub fn from_user_agent(user_agent: &str) -> Self {
+        match woothee::parser::Parser::new().parse(user_agent) {
+            Some(result) => DeviceInfo {
+                browser: result.name.to_string(),
+                browser_version: result.version.to_string(),
+                os: result.os.to_string(),
+                os_version: result.os_version.to_string(),
+                device_type: result.category.to_string(),
+            },
+            None => DeviceInfo::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Location {
+    // ISO 3166-1 alpha-2 code
+    pub country: Option<String>,
+    pub city: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SessionMetadata {
     pub user_agent: String,
+    // it's the service host the client requested, not the client one
     pub user_host: String,
+    #[serde(default)]
+    pub device: DeviceInfo,
+    #[serde(default)]
+    pub location: Option<Location>,
+    #[serde(default)]
+    pub first_seen_ip: Option<String>,
+    #[serde(default)]
+    pub last_seen_ip: Option<String>,
 }
 
 impl SessionMetadata {
@@ -14,13 +67,45 @@ impl SessionMetadata {
         SessionMetadata {
             user_agent: UNKNOWN_DATA.to_string(),
             user_host: UNKNOWN_DATA.to_string(),
+            device: DeviceInfo::default(),
+            location: None,
+            first_seen_ip: None,
+            last_seen_ip: None,
+        }
+    }
+
+    /// keeps the ip the session was created from, returns true in case the last seen ip is changed
+    pub fn set_client_ip(&mut self, client_ip: String, location: Option<Location>) -> bool {
+        if self.first_seen_ip.is_none() {
+            self.first_seen_ip = Some(client_ip.clone());
+        }
+        if self.last_seen_ip.as_ref() == Some(&client_ip) {
+            return false;
+        }
+        self.last_seen_ip = Some(client_ip);
+        if location.is_some() {
+            self.location = location;
+        }
+        true
+    }
+
+    /// updates the session client ip by the current request one
+    pub fn update_client_ip(&mut self, request_metadata: &SessionMetadata) -> bool {
+        match &request_metadata.last_seen_ip {
+            Some(client_ip) => {
+                self.set_client_ip(client_ip.clone(), request_metadata.location.clone())
+            }
+            None => false,
         }
     }
     pub fn set_metadata_from_request(&mut self, request: &HttpRequest) -> () {
         let mut head = request.head().clone();
         if let Some(user_agent_value) = head.headers_mut().get(header::USER_AGENT) {
             match user_agent_value.to_str() {
-                Ok(user_agent) => self.user_agent = user_agent.to_string(),
+                Ok(user_agent) => {
+                    self.user_agent = user_agent.to_string();
+                    self.device = DeviceInfo::from_user_agent(user_agent);
+                }
                 Err(err) => {
                     log::debug!(
                         "Unable to get USER_AGENT string from : {:?}, error: {}",
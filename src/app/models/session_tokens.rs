@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::{common::AuthProviders, token::Token};
@@ -39,6 +40,25 @@ impl SessionTokens {
             _ => self.access_token.is_some() && self.refresh_token.is_some(),
         }
     }
+
+    /// returns the time the session can't be continued after, None means no limit:
+    /// expired access token can be refreshed till refresh token expiration(Google refresh token
+    /// has no expiration, CyberSherlock one does), without refresh token(Facebook)
+    /// the session lives till access token expiration
+    pub fn get_expiration_limit(&self) -> Option<DateTime<Utc>> {
+        match &self.refresh_token {
+            Some(refresh_token) => refresh_token.expire,
+            None => self.access_token.as_ref().and_then(|token| token.expire),
+        }
+    }
+
+    pub fn is_access_token_expired(&self) -> bool {
+        self.access_token
+            .as_ref()
+            .and_then(|token| token.expire)
+            .map_or(false, |expire| expire <= Utc::now())
+    }
+
     pub fn empty_tokens() -> SessionTokens {
         SessionTokens {
             access_token: None,
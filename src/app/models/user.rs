@@ -8,11 +8,19 @@ use redis::{
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use crate::app::services::storage::service::CollectionType;
+use crate::app::{
+    services::storage::service::CollectionType, shared::normalization::normalize_email,
+};
 
 use super::{
-    common::{datetime_as_mongo_bson, AuthProviders},
-    user_profile::{CyberSherlockProfile, FacebookProfile, GoogleProfile, UserProfile},
+    common::{datetime_as_mongo_bson, optional_datetime_as_mongo_bson, AuthProviders},
+    login_record::LoginRecord,
+    role::{get_roles_permissions, Role},
+    user_audit::UserAuditRecord,
+    user_profile::{
+        CyberSherlockProfile, DisplayProfile, FacebookProfile, GoogleProfile, UserProfile,
+    },
+    user_status::{Suspension, UserStatus},
 };
 pub type UserId = ObjectId;
 
@@ -27,6 +35,33 @@ pub struct User {
     pub google: Option<GoogleProfile>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub facebook: Option<FacebookProfile>,
+    #[serde(default)]
+    pub audit_trail: Vec<UserAuditRecord>,
+    // the latest logins, the oldest ones are dropped by LOGIN_HISTORY_SIZE
+    #[serde(default)]
+    pub login_history: Vec<LoginRecord>,
+    // soft deleted user is restorable till purge_at
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_datetime_as_mongo_bson"
+    )]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_datetime_as_mongo_bson"
+    )]
+    pub purge_at: Option<DateTime<Utc>>,
+    // set while the user is suspended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspension: Option<Suspension>,
+    // user own max active sessions(paid tiers), overrides the default limit, 0 means no limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_sessions: Option<u64>,
+    // no roles means regular user with access to own data only
+    #[serde(default)]
+    pub roles: Vec<Role>,
     #[serde(with = "datetime_as_mongo_bson")]
     pub created_at: DateTime<Utc>,
     #[serde(with = "datetime_as_mongo_bson")]
@@ -42,6 +77,13 @@ impl User {
             cyber_sherlock: None,
             google: None,
             facebook: None,
+            audit_trail: vec![],
+            login_history: vec![],
+            deleted_at: None,
+            purge_at: None,
+            suspension: None,
+            max_sessions: None,
+            roles: vec![],
             created_at: now,
             updated_at: now,
         };
@@ -71,6 +113,15 @@ impl User {
             "cyber_sherlock": self.cyber_sherlock,
             "google": self.google,
             "facebook": self.facebook,
+            "audit_trail": self.audit_trail,
+            "login_history": self.login_history,
+            "deleted_at": self.deleted_at.map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+            "purge_at": self.purge_at.map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+            // the status isn't stored, it's derived from the deletion and suspension
+            "status": self.get_status(),
+            "suspension": self.suspension,
+            "max_sessions": self.max_sessions,
+            "roles": self.roles,
             "created_at": self.created_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
             "updated_at": self.updated_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
         })
@@ -84,9 +135,128 @@ impl User {
         if self.cyber_sherlock.is_some() {
             *json_user.get_mut("cyber_sherlock").unwrap() = cyber_sherlock;
         }
+        json_user["display_profile"] = json!(self.get_display_profile());
         json_user
     }
 
+    pub fn get_display_profile(&self) -> Option<DisplayProfile> {
+        // in case active profile is not linked(it shouldn't be) it takes the first linked one
+        self.get_profile_display_data(&self.active_profile)
+            .or_else(|| {
+                self.get_linked_providers()
+                    .iter()
+                    .find_map(|provider| self.get_profile_display_data(provider))
+            })
+    }
+
+    fn get_profile_display_data(&self, provider: &AuthProviders) -> Option<DisplayProfile> {
+        match provider {
+            AuthProviders::CyberSherlock => {
+                self.cyber_sherlock.as_ref().map(|profile| DisplayProfile {
+                    name: profile.name.clone(),
+                    email: profile.email.clone(),
+                    picture: profile.picture.clone(),
+                })
+            }
+            AuthProviders::Google => self.google.as_ref().map(|profile| DisplayProfile {
+                name: profile.name.clone(),
+                email: Some(profile.email.clone()),
+                picture: profile.picture.clone(),
+            }),
+            AuthProviders::Facebook => self.facebook.as_ref().map(|profile| DisplayProfile {
+                name: profile.name.clone(),
+                email: profile.email.clone(),
+                picture: profile.picture.clone(),
+            }),
+        }
+    }
+
+    /// returns the first verified email of linked profiles
+    pub fn get_verified_email(&self) -> Option<String> {
+        self.get_linked_providers()
+            .iter()
+            .filter_map(|provider| self.get_profile(provider))
+            .find_map(|profile| UserProfile::get_verified_email(&profile))
+    }
+
+    /// checks the email is verified by any linked profile, emails are compared normalized
+    pub fn has_verified_email(&self, email: &str) -> bool {
+        let normalized_email = normalize_email(email).unwrap_or(email.to_string());
+        self.get_linked_providers()
+            .iter()
+            .filter_map(|provider| self.get_profile(provider))
+            .filter_map(|profile| UserProfile::get_verified_email(&profile))
+            .any(|verified_email| {
+                normalize_email(&verified_email).unwrap_or(verified_email) == normalized_email
+            })
+    }
+
+    pub fn get_permissions(&self) -> Vec<String> {
+        get_roles_permissions(&self.roles)
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.roles
+            .iter()
+            .any(|role| role.get_permissions().contains(&permission))
+    }
+
+    pub fn get_role_names(&self) -> Vec<String> {
+        self.roles.iter().map(|role| role.to_string()).collect()
+    }
+
+    pub fn is_deletion_scheduled(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// returns the effective user status, expired suspension makes the user active again
+    pub fn get_status(&self) -> UserStatus {
+        // soft deleted user is pending deletion till it's restored or purged
+        if self.is_deletion_scheduled() {
+            return UserStatus::PendingDeletion;
+        }
+        if self.is_suspended() {
+            return UserStatus::Suspended;
+        }
+        UserStatus::Active
+    }
+
+    /// expired suspension doesn't suspend the user anymore
+    pub fn is_suspended(&self) -> bool {
+        self.suspension
+            .as_ref()
+            .is_some_and(|suspension| !suspension.is_expired())
+    }
+
+    pub fn has_profile(&self, provider: &AuthProviders) -> bool {
+        match provider {
+            AuthProviders::CyberSherlock => self.cyber_sherlock.is_some(),
+            AuthProviders::Google => self.google.is_some(),
+            AuthProviders::Facebook => self.facebook.is_some(),
+        }
+    }
+
+    pub fn get_profile(&self, provider: &AuthProviders) -> Option<UserProfile> {
+        match provider {
+            AuthProviders::CyberSherlock => {
+                self.cyber_sherlock.clone().map(UserProfile::CyberSherlock)
+            }
+            AuthProviders::Google => self.google.clone().map(UserProfile::Google),
+            AuthProviders::Facebook => self.facebook.clone().map(UserProfile::Facebook),
+        }
+    }
+
+    pub fn get_linked_providers(&self) -> Vec<AuthProviders> {
+        vec![
+            AuthProviders::CyberSherlock,
+            AuthProviders::Google,
+            AuthProviders::Facebook,
+        ]
+        .into_iter()
+        .filter(|provider| self.has_profile(provider))
+        .collect()
+    }
+
     pub fn get_user_cache_key(user_id: &str) -> String {
         format!("user::{}", user_id)
     }
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::common::{datetime_as_mongo_bson, AuthProviders};
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub enum UserAuditEvent {
+    // provider profile was attached automatically by verified email
+    ProfileMerged,
+    // provider profile was attached after user confirmation
+    ProfileMergeConfirmed,
+    // another user account was merged into the user by admin
+    AccountsMerged,
+    // user requested account deletion, account is restorable during grace period
+    AccountDeletionScheduled,
+    // user logged in during deletion grace period
+    AccountRestored,
+    // user roles were set by admin
+    RolesChanged,
+    // user own max sessions limit was set or removed by admin
+    MaxSessionsChanged,
+    // user was suspended by admin, the user can't login till the suspension ends
+    AccountSuspended,
+    // user suspension was lifted by admin
+    AccountReactivated,
+    // user password was reset by the token sent on admin request
+    PasswordReset,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserAuditRecord {
+    pub event: UserAuditEvent,
+    pub auth_provider: AuthProviders,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    #[serde(with = "datetime_as_mongo_bson")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl UserAuditRecord {
+    pub fn new(
+        event: UserAuditEvent,
+        auth_provider: AuthProviders,
+        details: Option<String>,
+    ) -> Self {
+        UserAuditRecord {
+            event,
+            auth_provider,
+            details,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value as RedisValue};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::{
+    common::AuthProviders,
+    login_record::LoginRecord,
+    session::Session,
+    session_metadata::SessionMetadata,
+    user::{User, UserId},
+};
+
+/// Session data exported to the user, session id and tokens are secrets and not exported
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedSession {
+    pub auth_provider: AuthProviders,
+    pub metadata: SessionMetadata,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Everything the service keeps about the user(GDPR data export),
+/// there are no consents in the export as the service doesn't collect any
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserDataExport {
+    pub user_id: UserId,
+    // user json keeps all provider profiles and audit trail without password hash
+    pub user: Value,
+    pub sessions: Vec<ExportedSession>,
+    pub login_history: Vec<LoginRecord>,
+    pub exported_at: DateTime<Utc>,
+}
+
+impl UserDataExport {
+    pub fn new(user: &User, sessions: Vec<Session>) -> Self {
+        UserDataExport {
+            user_id: user.id,
+            user: user.to_json(),
+            sessions: sessions
+                .into_iter()
+                .map(|session| ExportedSession {
+                    auth_provider: session.auth_provider,
+                    metadata: session.metadata,
+                    created_at: session.created_at,
+                    updated_at: session.updated_at,
+                })
+                .collect(),
+            login_history: user.login_history.clone(),
+            exported_at: Utc::now(),
+        }
+    }
+
+    pub fn generate_export_token() -> String {
+        Uuid::new_v4().to_string()
+    }
+
+    pub fn get_user_data_export_key(export_token: &str) -> String {
+        format!("user::exports::{}", export_token)
+    }
+
+    pub fn get_file_name(&self) -> String {
+        format!("user-data-{}.json", self.user_id)
+    }
+}
+
+impl FromRedisValue for UserDataExport {
+    fn from_redis_value(value: &RedisValue) -> RedisResult<UserDataExport> {
+        match *value {
+            RedisValue::Data(ref data) => Ok(serde_json::from_slice::<UserDataExport>(data)?),
+            _ => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "Response was of incompatible type",
+                format!("(response was {:?})", value),
+            ))),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::user::UserId;
+
+/// Redis Pub/Sub channel other services subscribe to for user lifecycle events
+pub const USER_EVENTS_CHANNEL: &str = "user::events";
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub enum UserEventType {
+    UserDeletionScheduled,
+    UserDeleted,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserEvent {
+    pub event: UserEventType,
+    pub user_id: UserId,
+    pub created_at: DateTime<Utc>,
+}
+
+impl UserEvent {
+    pub fn new(event: UserEventType, user_id: UserId) -> Self {
+        UserEvent {
+            event,
+            user_id,
+            created_at: Utc::now(),
+        }
+    }
+}
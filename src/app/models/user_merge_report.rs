@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+use super::{common::AuthProviders, user::UserId};
+
+/// Result of merging source user into target user,
+/// in dry run mode it describes changes without applying them
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserMergeReport {
+    pub target_user_id: UserId,
+    pub source_user_id: UserId,
+    pub dry_run: bool,
+    // source user profiles which are copied to target user
+    pub copied_profiles: Vec<AuthProviders>,
+    // both users have the profiles, target user keeps its own ones
+    pub conflicting_profiles: Vec<AuthProviders>,
+    // source user sessions which are moved to target user
+    pub moved_sessions: usize,
+    // source user sessions of conflicting profiles which are removed
+    pub removed_sessions: usize,
+}
@@ -2,6 +2,8 @@ use bson::doc;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use crate::app::shared::normalization::{normalize_email, normalize_phone};
+
 use super::{common::AuthProviders, user::UserId};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -14,6 +16,15 @@ pub struct CyberSherlockProfile {
     pub phone_verified: bool,
     pub picture: Option<String>,
     pub hash: String,
+    // canonical email and phone(E.164) to search users by
+    #[serde(default)]
+    pub normalized_email: Option<String>,
+    #[serde(default)]
+    pub normalized_phone: Option<String>,
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 impl CyberSherlockProfile {
     pub fn to_json(&self) -> Value {
@@ -25,10 +36,30 @@ impl CyberSherlockProfile {
             "phone": self.phone,
             "phone_verified": self.phone_verified,
             "picture": self.picture,
+            "locale": self.locale,
+            "timezone": self.timezone,
         })
     }
 }
 
+/// CyberSherlock profile fields editable by the user, None means the field is not changed
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CyberSherlockProfileUpdate {
+    pub name: Option<String>,
+    pub picture: Option<String>,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+}
+
+impl CyberSherlockProfileUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.picture.is_none()
+            && self.locale.is_none()
+            && self.timezone.is_none()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GoogleProfile {
     pub user_id: String,
@@ -36,6 +67,8 @@ pub struct GoogleProfile {
     pub email: String,
     pub email_verified: bool,
     pub picture: Option<String>,
+    #[serde(default)]
+    pub normalized_email: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -44,6 +77,16 @@ pub struct FacebookProfile {
     pub name: String,
     pub email: Option<String>,
     pub picture: Option<String>,
+    #[serde(default)]
+    pub normalized_email: Option<String>,
+}
+
+/// User data to show on client side, it is taken from the user active profile
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DisplayProfile {
+    pub name: String,
+    pub email: Option<String>,
+    pub picture: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -61,4 +104,58 @@ impl UserProfile {
             _ => AuthProviders::CyberSherlock,
         }
     }
+
+    /// sets normalized values of profile email and phone, original values stay as is
+    pub fn normalize(profile: UserProfile, phone_default_region: &str) -> UserProfile {
+        match profile {
+            UserProfile::CyberSherlock(mut cyber_sherlock_profile) => {
+                cyber_sherlock_profile.normalized_email = cyber_sherlock_profile
+                    .email
+                    .as_ref()
+                    .and_then(|email| normalize_email(email));
+                cyber_sherlock_profile.normalized_phone = cyber_sherlock_profile
+                    .phone
+                    .as_ref()
+                    .and_then(|phone| normalize_phone(phone, phone_default_region));
+                UserProfile::CyberSherlock(cyber_sherlock_profile)
+            }
+            UserProfile::Google(mut google_profile) => {
+                google_profile.normalized_email = normalize_email(&google_profile.email);
+                UserProfile::Google(google_profile)
+            }
+            UserProfile::Facebook(mut facebook_profile) => {
+                facebook_profile.normalized_email = facebook_profile
+                    .email
+                    .as_ref()
+                    .and_then(|email| normalize_email(email));
+                UserProfile::Facebook(facebook_profile)
+            }
+        }
+    }
+
+    pub fn get_email(profile: &UserProfile) -> Option<String> {
+        match profile {
+            UserProfile::CyberSherlock(cyber_sherlock_profile) => {
+                cyber_sherlock_profile.email.clone()
+            }
+            UserProfile::Google(google_profile) => Some(google_profile.email.clone()),
+            UserProfile::Facebook(facebook_profile) => facebook_profile.email.clone(),
+        }
+    }
+
+    /// returns profile email in case the email is verified by provider
+    pub fn get_verified_email(profile: &UserProfile) -> Option<String> {
+        match profile {
+            UserProfile::CyberSherlock(cyber_sherlock_profile)
+                if cyber_sherlock_profile.email_verified =>
+            {
+                cyber_sherlock_profile.email.clone()
+            }
+            UserProfile::Google(google_profile) if google_profile.email_verified => {
+                Some(google_profile.email.clone())
+            }
+            // Facebook doesn't provide email verification status
+            _ => None,
+        }
+    }
 }
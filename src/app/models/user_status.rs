@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    common::{datetime_as_mongo_bson, optional_datetime_as_mongo_bson},
+    user::UserId,
+};
+
+/// user account state, only active user can login and use the sessions
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UserStatus {
+    #[default]
+    Active,
+    // suspended by admin, details are kept in user suspension
+    Suspended,
+    // soft deleted user in deletion grace period
+    PendingDeletion,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Suspension {
+    pub reason: String,
+    // None means till the user is reactivated by admin
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_datetime_as_mongo_bson"
+    )]
+    pub until: Option<DateTime<Utc>>,
+    // admin who suspended the user
+    pub suspended_by: UserId,
+    #[serde(with = "datetime_as_mongo_bson")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Suspension {
+    pub fn new(reason: String, until: Option<DateTime<Utc>>, suspended_by: UserId) -> Self {
+        Suspension {
+            reason,
+            until,
+            suspended_by,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.until.is_some_and(|until| until <= Utc::now())
+    }
+}
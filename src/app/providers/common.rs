@@ -4,7 +4,10 @@ use actix_web::web;
 use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value as RedisValue};
 use serde::{Deserialize, Serialize};
 
-use crate::app::{models::session::Session, services::cache::service::RedisCacheService};
+use crate::app::{
+    models::session::{Session, SessionDelivery},
+    services::cache::service::RedisCacheService,
+};
 
 use super::error::ProviderError;
 
@@ -43,6 +46,9 @@ pub fn parse_callback_query_string(query_string: &str) -> Result<CallbackQueryDa
 pub struct LoginCacheData {
     pub pkce_code_verifier: String,
     pub session: Session,
+    // requested on login, the callback is the provider redirect without client headers
+    #[serde(default)]
+    pub session_delivery: SessionDelivery,
 }
 
 impl FromRedisValue for LoginCacheData {
@@ -63,10 +69,9 @@ pub fn get_login_cache_data_by_state(
     state: &str,
 ) -> Result<LoginCacheData, ProviderError> {
     // process code and state
-    let login_cache_data_value = cache_service.get_value::<LoginCacheData>(state.as_ref())?;
+    // cache auth data is deleted on getting to avoid using it again by concurrent callbacks
+    let login_cache_data_value = cache_service.take_value::<LoginCacheData>(state.as_ref())?;
     if let Some(login_cache_data) = login_cache_data_value {
-        // delete cache auth data to avoid using it again
-        cache_service.delete_values(vec![state.to_string()])?;
         Ok(login_cache_data)
     } else {
         log::debug!("No callback request state {} in Redis", state);
@@ -6,7 +6,11 @@ use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value as RedisVa
 use serde::{Deserialize, Serialize};
 use validator::{Validate, ValidationError};
 
-use crate::app::models::session::Session;
+use crate::app::models::{
+    role::Role,
+    session::{Session, SessionDelivery},
+    user::UserId,
+};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AccessTokenClaims {
@@ -17,6 +21,11 @@ pub struct AccessTokenClaims {
     pub phone: Option<String>,
     pub phone_verified: bool,
     pub name: String,
+    // user roles and their permissions at the token issue time
+    #[serde(default)]
+    pub roles: Vec<Role>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
     pub exp: i64,
 }
 
@@ -87,10 +96,101 @@ impl LoginQueryData {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct ChangeEmailQueryData {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct ChangePhoneQueryData {
+    #[validate(phone)]
+    pub phone: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct ConfirmContactChangeQueryData {
+    #[validate(length(equal = 6))]
+    pub code: String,
+}
+
+/// new user email or phone to confirm by code
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ContactChange {
+    Email(String),
+    Phone(String),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PendingContactChange {
+    pub user_id: UserId,
+    pub change: ContactChange,
+    pub code: String,
+    // wrong code attempts
+    pub attempts: u8,
+}
+
+impl PendingContactChange {
+    pub fn get_pending_contact_change_key(user_id: &UserId) -> String {
+        format!("cyber_sherlock::contact_changes::{}", user_id)
+    }
+}
+
+impl FromRedisValue for PendingContactChange {
+    fn from_redis_value(value: &RedisValue) -> RedisResult<PendingContactChange> {
+        match *value {
+            RedisValue::Data(ref data) => Ok(serde_json::from_slice::<PendingContactChange>(data)?),
+            _ => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "Response was of incompatible type",
+                format!("(response was {:?})", value),
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct ResetPasswordQueryData {
+    #[validate(length(equal = 64))]
+    pub token: String,
+    #[validate(length(min = 8, max = 128))]
+    pub password: String,
+    #[validate(must_match(other = "password"))]
+    pub verify_password: String,
+}
+
+/// password reset requested for the user, it's kept by one-time token
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PasswordReset {
+    pub user_id: UserId,
+}
+
+impl PasswordReset {
+    pub fn get_password_reset_key(token: &str) -> String {
+        format!("cyber_sherlock::password_resets::{}", token)
+    }
+}
+
+impl FromRedisValue for PasswordReset {
+    fn from_redis_value(value: &RedisValue) -> RedisResult<PasswordReset> {
+        match *value {
+            RedisValue::Data(ref data) => Ok(serde_json::from_slice::<PasswordReset>(data)?),
+            _ => Err(RedisError::from((
+                ErrorKind::TypeError,
+                "Response was of incompatible type",
+                format!("(response was {:?})", value),
+            ))),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RegisterCacheData {
     pub pkce_code_verifier: String,
     pub session: Session,
+    // requested on register, the callback is the confirmation link without client headers
+    #[serde(default)]
+    pub session_delivery: SessionDelivery,
     pub hash: String,
     pub email: Option<String>,
     pub phone: Option<String>,
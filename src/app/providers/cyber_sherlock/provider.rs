@@ -1,27 +1,37 @@
 use chrono::{Duration, Utc};
 use oauth2::PkceCodeChallenge;
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng as _};
 use url::Url;
 
 use crate::{
     app::{
         models::{
-            session::Session, session_tokens::SessionTokens, token::Token,
+            role::{get_roles_permissions, Role},
+            session::{Session, SessionDelivery},
+            session_tokens::SessionTokens,
+            token::Token,
+            user::UserId,
             user_profile::CyberSherlockProfile,
         },
         providers::{error::ProviderError, notification::provider::NotificationProvider},
         services::cache::service::RedisCacheService,
-        shared::jwt::encode_claims,
+        shared::jwt::{decode_token_with_secret, encode_claims},
     },
     config::app_config::AppConfig,
 };
 
 use super::{
     common::{
-        hash_password, AccessTokenClaims, RefreshTokenClaims, RegisterCacheData, RegisterQueryData,
+        hash_password, AccessTokenClaims, ContactChange, PasswordReset, PendingContactChange,
+        RefreshTokenClaims, RegisterCacheData, RegisterQueryData, ResetPasswordQueryData,
     },
     error::CyberSherlockAuthProviderError,
 };
 
+// wrong code attempts before pending contact change is dropped
+const CONTACT_CHANGE_MAX_ATTEMPTS: u8 = 5;
+const PASSWORD_RESET_TOKEN_LENGTH: usize = 64;
+
 pub struct CyberSherlockAuthProvider {
     cache_service: RedisCacheService,
     config: AppConfig,
@@ -45,6 +55,7 @@ impl CyberSherlockAuthProvider {
         &mut self,
         register_query_data: &RegisterQueryData,
         session: Session,
+        session_delivery: SessionDelivery,
     ) -> Result<String, ProviderError> {
         // Create a PKCE code verifier and SHA-256 encode it as a code challenge.
         let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
@@ -57,6 +68,7 @@ impl CyberSherlockAuthProvider {
         let register_cache_data = RegisterCacheData {
             pkce_code_verifier: pkce_code_verifier.secret().to_string(),
             session,
+            session_delivery,
             //TODO: investigate how to optimize pwd hashing to decrease operation time
             hash: hash_password(register_query_data.password.as_str()).map_err(|_| {
                 ProviderError::CyberSherlockAuthProviderError(
@@ -106,19 +118,151 @@ impl CyberSherlockAuthProvider {
         &mut self,
         code: &str,
     ) -> Result<RegisterCacheData, ProviderError> {
-        let data = self.cache_service.get_value::<RegisterCacheData>(code)?;
+        // cache register data is deleted on getting to avoid using it again
+        let data = self.cache_service.take_value::<RegisterCacheData>(code)?;
         log::debug!("{:?}", &data);
         if let Some(register_data) = data {
-            // delete cache register data to avoid using it again
-            self.cache_service.delete_values(vec![code.to_string()])?;
             return Ok(register_data);
         }
         Err(ProviderError::CallbackStateCacheError)
     }
 
+    /// keeps pending email/phone change and sends confirmation code to the new destination,
+    /// the old destination gets a notice about the change
+    pub fn request_contact_change(
+        &mut self,
+        user_profile: &CyberSherlockProfile,
+        change: ContactChange,
+    ) -> Result<(), ProviderError> {
+        let code = format!("{:06}", OsRng.gen_range(0..1_000_000));
+        let pending_contact_change = PendingContactChange {
+            user_id: user_profile.user_id,
+            change: change.clone(),
+            code: code.clone(),
+            attempts: 0,
+        };
+        // a new request replaces the previous pending change
+        self.cache_service.set_value_with_ttl(
+            &PendingContactChange::get_pending_contact_change_key(&user_profile.user_id),
+            &pending_contact_change,
+            self.config.contact_change_ttl_sec,
+        )?;
+        //TODO: implement contact change messages to send not only code
+        match change {
+            ContactChange::Email(email) => {
+                self.notification_provider.send_email(&code, email)?;
+                if let Some(old_email) = user_profile.email.to_owned() {
+                    self.notification_provider
+                        .send_email("Email change is requested", old_email)?;
+                }
+            }
+            ContactChange::Phone(phone) => {
+                self.notification_provider.send_mobile(&code, phone)?;
+                if let Some(old_phone) = user_profile.phone.to_owned() {
+                    self.notification_provider
+                        .send_mobile("Phone change is requested", old_phone)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// returns confirmed contact change, None in case of no pending change or wrong code
+    pub fn confirm_contact_change(
+        &mut self,
+        user_id: &UserId,
+        code: &str,
+    ) -> Result<Option<ContactChange>, ProviderError> {
+        let key = PendingContactChange::get_pending_contact_change_key(user_id);
+        let mut pending_contact_change =
+            match self.cache_service.get_value::<PendingContactChange>(&key)? {
+                Some(pending_contact_change) => pending_contact_change,
+                None => return Ok(None),
+            };
+        if pending_contact_change.code == code {
+            // delete pending change to avoid using it again
+            self.cache_service.delete_values(vec![key])?;
+            return Ok(Some(pending_contact_change.change));
+        }
+        pending_contact_change.attempts += 1;
+        if pending_contact_change.attempts >= CONTACT_CHANGE_MAX_ATTEMPTS {
+            log::debug!(
+                "Contact change of User {} is dropped, too many attempts",
+                user_id
+            );
+            self.cache_service.delete_values(vec![key])?;
+        } else {
+            let ttl = self.cache_service.get_ttl(&key)?;
+            if ttl > 0 {
+                self.cache_service
+                    .set_value_with_ttl(&key, &pending_contact_change, ttl as u64)?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// keeps one-time password reset token and sends it to the user verified email or phone,
+    /// returns false in case the user has no verified contact to send the token
+    pub fn request_password_reset(
+        &mut self,
+        user_profile: &CyberSherlockProfile,
+    ) -> Result<bool, ProviderError> {
+        let token: String = (0..PASSWORD_RESET_TOKEN_LENGTH)
+            .map(|_| OsRng.sample(Alphanumeric) as char)
+            .collect();
+        let password_reset = PasswordReset {
+            user_id: user_profile.user_id,
+        };
+        //TODO: implement password reset messages to send not only token
+        match (&user_profile.email, &user_profile.phone) {
+            (Some(email), _) if user_profile.email_verified => {
+                self.cache_service.set_value_with_ttl(
+                    &PasswordReset::get_password_reset_key(&token),
+                    &password_reset,
+                    self.config.password_reset_ttl_sec,
+                )?;
+                self.notification_provider
+                    .send_email(&token, email.to_owned())?;
+            }
+            (_, Some(phone)) if user_profile.phone_verified => {
+                self.cache_service.set_value_with_ttl(
+                    &PasswordReset::get_password_reset_key(&token),
+                    &password_reset,
+                    self.config.password_reset_ttl_sec,
+                )?;
+                self.notification_provider
+                    .send_mobile(&token, phone.to_owned())?;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// returns the user id and the new password hash by the reset token,
+    /// None in case the token is invalid or expired, the token is valid once
+    pub fn reset_password(
+        &self,
+        query_data: &ResetPasswordQueryData,
+    ) -> Result<Option<(UserId, String)>, ProviderError> {
+        let password_reset = match self.cache_service.take_value::<PasswordReset>(
+            &PasswordReset::get_password_reset_key(&query_data.token),
+        )? {
+            Some(password_reset) => password_reset,
+            None => return Ok(None),
+        };
+        let hash = hash_password(query_data.password.as_str()).map_err(|_| {
+            ProviderError::CyberSherlockAuthProviderError(
+                CyberSherlockAuthProviderError::Argon2PassHashError,
+            )
+        })?;
+        Ok(Some((password_reset.user_id, hash)))
+    }
+
+    /// issues access token with the user roles and permissions claims and refresh token
     pub fn get_tokens(
         &self,
         user_profile: &CyberSherlockProfile,
+        roles: &[Role],
     ) -> Result<SessionTokens, ProviderError> {
         let config = self.config.clone();
         let profile = user_profile.clone();
@@ -132,6 +276,8 @@ impl CyberSherlockAuthProvider {
             phone: profile.phone,
             phone_verified: user_profile.phone_verified,
             name: profile.name,
+            roles: roles.to_vec(),
+            permissions: get_roles_permissions(roles),
             exp: access_token_expiration.timestamp(),
         };
         let access_token_string = encode_claims::<AccessTokenClaims>(
@@ -166,6 +312,30 @@ impl CyberSherlockAuthProvider {
         Ok(tokens)
     }
 
+    /// issues a new access token only, the session refresh token is kept
+    /// to do not extend the session over the refresh token expiration
+    pub fn refresh_tokens(
+        &self,
+        user_profile: &CyberSherlockProfile,
+        roles: &[Role],
+    ) -> Result<SessionTokens, ProviderError> {
+        let tokens = self.get_tokens(user_profile, roles)?;
+        Ok(SessionTokens {
+            refresh_token: None,
+            ..tokens
+        })
+    }
+
+    /// returns claims of the valid not expired access token issued by `get_tokens`
+    pub fn verify_access_token(&self, token: &str) -> Result<AccessTokenClaims, ProviderError> {
+        let claims = decode_token_with_secret::<AccessTokenClaims>(
+            token,
+            &self.config.jwt_secret,
+            &self.config.app_id,
+        )?;
+        Ok(claims)
+    }
+
     pub fn create_user_profile(
         &self,
         user_data: &RegisterCacheData,
@@ -179,6 +349,10 @@ impl CyberSherlockAuthProvider {
             phone_verified: false,
             picture: None,
             hash: user_data.hash.clone(),
+            normalized_email: None,
+            normalized_phone: None,
+            locale: None,
+            timezone: None,
         };
         Ok(user_profile)
     }
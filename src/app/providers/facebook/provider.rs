@@ -5,7 +5,7 @@ use actix_web::http::Method;
 use oauth2::url::Url;
 use oauth2::{HttpRequest, PkceCodeChallenge};
 
-use crate::app::models::session::Session;
+use crate::app::models::session::{Session, SessionDelivery};
 use crate::app::models::session_tokens::SessionTokens;
 use crate::app::models::token::Token;
 use crate::app::models::user_profile::FacebookProfile;
@@ -43,6 +43,7 @@ impl FacebookProvider {
     pub fn get_authorization_url_data(
         &mut self,
         session: Session,
+        session_delivery: SessionDelivery,
     ) -> Result<String, ProviderError> {
         // Create a PKCE code verifier and SHA-256 encode it as a code challenge.
         let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
@@ -67,6 +68,7 @@ impl FacebookProvider {
         let login_cache_data = LoginCacheData {
             pkce_code_verifier: pkce_code_verifier.secret().to_string(),
             session,
+            session_delivery,
         };
         self.set_auth_data_to_cache(pkce_code_challenge.as_str(), &login_cache_data)?;
 
@@ -149,6 +151,7 @@ impl FacebookProvider {
             name: user_profile_data.name,
             email: user_profile_data.email,
             picture: None,
+            normalized_email: None,
         };
 
         if let Some(profile_picture_data) = user_profile_data.picture {
@@ -39,6 +39,16 @@ impl<'a> fmt::Display for DisplayGoogleCerts<'a> {
     }
 }
 
+// the refresh token is expired or revoked, it can't be used anymore
+pub const INVALID_GRANT_ERROR: &str = "invalid_grant";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GoogleTokenErrorResponse {
+    pub error: String,
+    #[serde(default)]
+    pub error_description: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GoogleTokenResponse {
     pub access_token: String,
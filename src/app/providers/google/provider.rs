@@ -1,4 +1,5 @@
 use chrono::{DateTime, Utc};
+use std::future::Future;
 use std::str::FromStr;
 
 use actix_web::http::Method;
@@ -25,7 +26,7 @@ use crate::config::google_config::GoogleConfig;
 use super::super::error::ProviderError;
 use super::common::{
     get_decoding_key_from_vec_cert, get_session_tokens, GoogleCert, GoogleKeys,
-    GoogleTokenResponse, UserInfo,
+    GoogleTokenErrorResponse, GoogleTokenResponse, UserInfo, INVALID_GRANT_ERROR,
 };
 use super::error::GoogleProviderError;
 
@@ -178,6 +179,56 @@ impl GoogleProvider {
         Ok(tokens)
     }
 
+    /// Google refresh token has no expiration, it's valid till user revokes it,
+    /// the response has no refresh token so the current one has to be kept
+    /// None means the refresh token is rejected by Google(expired or revoked),
+    /// other fails are transient and the refresh can be retried,
+    /// the request is built on the provider but awaited without it, so the provider lock isn't held
+    pub fn refresh_tokens(
+        &self,
+        refresh_token: &str,
+    ) -> Result<
+        impl Future<Output = Result<Option<SessionTokens>, ProviderError>> + 'static,
+        ProviderError,
+    > {
+        let url = Url::from_str(&self.config.google_token_url)?;
+        let params: Vec<(&str, &str)> = vec![
+            ("client_id", &self.config.google_client_id),
+            ("client_secret", &self.config.google_client_secret),
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token"),
+        ];
+        let request = HttpRequest {
+            method: Method::POST,
+            url,
+            headers: get_x_www_form_headers(),
+            body: url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(params)
+                .finish()
+                .into_bytes(),
+        };
+        Ok(async move {
+            let response = async_http_request(request)
+                .await
+                .map_err(|err| format!("Refresh token request error: {err}"))?;
+
+            if !response.status_code.is_success() {
+                if let Ok(error_response) =
+                    serde_json::from_slice::<GoogleTokenErrorResponse>(&response.body)
+                {
+                    if error_response.error == INVALID_GRANT_ERROR {
+                        log::debug!("Refresh token is rejected: {:?}", error_response);
+                        return Ok(None);
+                    }
+                }
+                return Err(ProviderError::OAuth2RequestTokenError);
+            }
+            let response = serde_json::from_slice::<GoogleTokenResponse>(&response.body)?;
+            let tokens = get_session_tokens(response);
+            Ok(Some(tokens))
+        })
+    }
+
     pub async fn get_user_profile(
         &mut self,
         token: Option<Token>,
@@ -219,6 +270,7 @@ impl GoogleProvider {
             email: token_data.email,
             email_verified: token_data.email_verified,
             picture: Some(token_data.picture),
+            normalized_email: None,
         })
     }
 
@@ -251,6 +303,7 @@ impl GoogleProvider {
             email: user_info.email,
             email_verified: user_info.email_verified,
             picture: Some(user_info.picture),
+            normalized_email: None,
         });
     }
 
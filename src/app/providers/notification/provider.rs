@@ -3,6 +3,7 @@ use crate::app::{providers::error::ProviderError, services::common::AsyncFn};
 use super::common::{EMAIL, MOBILE};
 
 //TODO: integrates with 3rd party to send registration emails and phone messages
+#[derive(Debug)]
 pub struct NotificationProvider {
     // async_http_request: Box<dyn AsyncFn>,
 }
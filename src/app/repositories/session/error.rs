@@ -9,6 +9,9 @@ pub enum SessionRepositoryError {
 
     #[error("SerdeJson error")]
     SerdeJsonError,
+
+    #[error("Session limit exceeded error")]
+    SessionLimitExceeded,
 }
 
 impl From<CacheServiceError> for SessionRepositoryError {
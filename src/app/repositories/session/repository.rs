@@ -1,7 +1,25 @@
-use crate::app::{models::session::Session, services::cache::service::RedisCacheService};
+use bson::oid::ObjectId;
+
+use crate::{
+    app::{
+        models::session::Session,
+        services::cache::service::RedisCacheService,
+        shared::metrics::{
+            SESSION_INDEXES_REMOVED, SESSION_INDEXES_SCANNED, SESSION_INDEX_SESSIONS_REMOVED,
+        },
+    },
+    config::session_config::{SessionLimit, SessionLimitPolicy},
+};
 
 use super::error::SessionRepositoryError;
 
+#[derive(Debug, Default)]
+pub struct SessionIndexCleanupReport {
+    pub scanned_indexes: usize,
+    pub removed_sessions: usize,
+    pub removed_indexes: usize,
+}
+
 #[derive(Debug)]
 pub struct SessionRepository {
     storage: RedisCacheService,
@@ -20,32 +38,146 @@ impl SessionRepository {
         if session_keys.len() == 0 {
             return Ok(vec![]);
         }
-        let keys = session_keys.keys().map(|key| key.to_owned()).collect();
-        let sessions = self.storage.get_values::<Session>(keys)?;
+        let keys: Vec<String> = session_keys.keys().map(|key| key.to_owned()).collect();
+        let sessions = self.storage.get_values::<Session>(keys.clone())?;
         // in case the session key is still in user session set but session is not in cach
-        // it returns Nil(None) and we filter the array
-        let sessions_without_none = sessions.iter().filter_map(|s| s.to_owned()).collect();
+        // it returns Nil(None), the dangling key is removed from the set
+        let dangling_keys: Vec<String> = keys
+            .into_iter()
+            .zip(sessions.iter())
+            .filter(|(_, s)| s.is_none())
+            .map(|(key, _)| key)
+            .collect();
+        if !dangling_keys.is_empty() {
+            log::debug!(
+                "Remove {} expired sessions from {}",
+                dangling_keys.len(),
+                user_sessions_key
+            );
+            SESSION_INDEX_SESSIONS_REMOVED.inc_by(dangling_keys.len() as u64);
+            // Redis removes the empty set itself
+            if dangling_keys.len() == sessions.len() {
+                SESSION_INDEXES_REMOVED.inc_by(1);
+            }
+            self.storage
+                .delete_values_and_set_values(user_sessions_key, dangling_keys)?;
+        }
+        let sessions_without_none = sessions.into_iter().flatten().collect();
         log::debug!("User sessions: {:?}", sessions_without_none);
         Ok(sessions_without_none)
     }
 
+    /// removes expired session keys from all user sessions sets and extends the set ttl
+    /// to the longest session one, the counts are added to the metrics
+    pub async fn cleanup_sessions_indexes(
+        &mut self,
+    ) -> Result<SessionIndexCleanupReport, SessionRepositoryError> {
+        let mut report = SessionIndexCleanupReport::default();
+        let user_sessions_keys = self
+            .storage
+            .scan_keys(&Session::get_user_sessions_key("*"))?;
+        for user_sessions_key in user_sessions_keys {
+            report.scanned_indexes += 1;
+            let session_keys: Vec<String> = self
+                .storage
+                .get_all_set_values(&user_sessions_key)?
+                .into_keys()
+                .collect();
+            let ttls = self.storage.get_ttls(&session_keys)?;
+            let mut dangling_keys = vec![];
+            let mut max_ttl: Option<i64> = Some(0);
+            for (session_key, ttl) in session_keys.iter().zip(ttls) {
+                match ttl {
+                    // session doesn't exist
+                    -2 => dangling_keys.push(session_key.to_owned()),
+                    // session without ttl keeps the set forever
+                    -1 => max_ttl = None,
+                    ttl => max_ttl = max_ttl.map(|max_ttl| max_ttl.max(ttl)),
+                }
+            }
+            if !dangling_keys.is_empty() {
+                report.removed_sessions += dangling_keys.len();
+                // Redis removes the empty set itself
+                if dangling_keys.len() == session_keys.len() {
+                    report.removed_indexes += 1;
+                }
+                self.storage
+                    .delete_values_and_set_values(&user_sessions_key, dangling_keys)?;
+            }
+            // the set ttl is only extended, a session added meanwhile could extend it already
+            if let Some(max_ttl) = max_ttl.filter(|max_ttl| *max_ttl > 0) {
+                self.storage
+                    .extend_ttl(&user_sessions_key, max_ttl as u64)?;
+            }
+        }
+        SESSION_INDEXES_SCANNED.inc_by(report.scanned_indexes as u64);
+        SESSION_INDEX_SESSIONS_REMOVED.inc_by(report.removed_sessions as u64);
+        SESSION_INDEXES_REMOVED.inc_by(report.removed_indexes as u64);
+        Ok(report)
+    }
+
+    /// sets the session keeping user active sessions number in the limit,
+    /// returns sessions evicted to free place for the new one
     pub async fn set_session(
         &mut self,
         session_key: &str,
         session: &Session,
         session_ttl: u64,
-    ) -> Result<(), SessionRepositoryError> {
-        // TODO: implement user sessions in cache array updating in parallel(in one step) with session setting
-        self.storage
-            .set_data_with_ttl::<Session>(session_key, session.clone(), session_ttl)?;
-        self.storage.set(
+        session_limit: Option<SessionLimit>,
+    ) -> Result<Vec<Session>, SessionRepositoryError> {
+        let mut evicted_sessions = vec![];
+        if let Some(session_limit) = session_limit {
+            evicted_sessions = self.enforce_session_limit(session, session_limit).await?;
+        }
+        // session and user sessions set are updated in one step to not leave orphans
+        self.storage.set_data_with_ttl_and_set_item::<Session>(
+            session_key,
+            session.clone(),
+            session_ttl,
             &Session::get_user_sessions_key(&session.user_id.to_string()),
             (
                 Session::get_session_key(&session.id).as_ref(),
                 session.auth_provider.to_string(),
             ),
         )?;
-        Ok(())
+        Ok(evicted_sessions)
+    }
+
+    async fn enforce_session_limit(
+        &mut self,
+        session: &Session,
+        session_limit: SessionLimit,
+    ) -> Result<Vec<Session>, SessionRepositoryError> {
+        let user_sessions_key = Session::get_user_sessions_key(&session.user_id.to_string());
+        let mut active_sessions: Vec<Session> = self
+            .get_sessions(&user_sessions_key)
+            .await?
+            .into_iter()
+            .filter(|s| s.id != session.id)
+            .collect();
+        if active_sessions.len() < session_limit.max_sessions {
+            return Ok(vec![]);
+        }
+        match session_limit.policy {
+            SessionLimitPolicy::Reject => return Err(SessionRepositoryError::SessionLimitExceeded),
+            SessionLimitPolicy::EvictOldest => active_sessions.sort_by_key(|s| s.created_at),
+            SessionLimitPolicy::EvictLeastRecentlyUsed => {
+                active_sessions.sort_by_key(|s| s.updated_at)
+            }
+        }
+        // the new session takes one place
+        let sessions_to_evict = active_sessions.len() + 1 - session_limit.max_sessions;
+        let evicted_sessions: Vec<Session> = active_sessions
+            .into_iter()
+            .take(sessions_to_evict)
+            .collect();
+        let evicted_session_keys = evicted_sessions
+            .iter()
+            .map(|s| Session::get_session_key(&s.id))
+            .collect();
+        self.remove_sessions(&user_sessions_key, evicted_session_keys)
+            .await?;
+        Ok(evicted_sessions)
     }
 
     pub async fn remove_sessions(
@@ -53,15 +185,73 @@ impl SessionRepository {
         user_sessions_key: &str,
         session_keys: Vec<String>,
     ) -> Result<(), SessionRepositoryError> {
-        // TODO: implement user sessions in cache array updating in parallel(in one step) with session deleting
         if session_keys.len() > 0 {
-            self.storage.delete_values(session_keys.clone())?;
             self.storage
-                .delete_set_values(user_sessions_key, session_keys)?;
+                .delete_values_and_set_values(user_sessions_key, session_keys)?;
         }
         Ok(())
     }
 
+    /// removes all user sessions except the one, returns number of removed session keys
+    pub async fn remove_sessions_except(
+        &mut self,
+        user_sessions_key: &str,
+        keep_session_key: &str,
+    ) -> Result<usize, SessionRepositoryError> {
+        let session_keys: Vec<String> = self
+            .storage
+            .get_all_set_values(user_sessions_key)?
+            .into_keys()
+            .filter(|key| key != keep_session_key)
+            .collect();
+        let removed_sessions = session_keys.len();
+        self.remove_sessions(user_sessions_key, session_keys)
+            .await?;
+        Ok(removed_sessions)
+    }
+
+    /// removes all user sessions(including expired ones left in the set) and the user sessions set
+    pub async fn remove_all_sessions(
+        &mut self,
+        user_sessions_key: &str,
+    ) -> Result<(), SessionRepositoryError> {
+        let mut keys: Vec<String> = self
+            .storage
+            .get_all_set_values(user_sessions_key)?
+            .into_keys()
+            .collect();
+        keys.push(user_sessions_key.to_string());
+        self.storage.delete_values(keys)?;
+        Ok(())
+    }
+
+    /// sets sessions to another user keeping their ttl and removes old user sessions set,
+    /// returns number of moved sessions
+    pub async fn move_sessions(
+        &mut self,
+        from_user_sessions_key: &str,
+        to_user_id: &ObjectId,
+        sessions: Vec<Session>,
+    ) -> Result<usize, SessionRepositoryError> {
+        let mut moved_sessions = 0;
+        for mut session in sessions {
+            let session_key = Session::get_session_key(&session.id);
+            let session_ttl = self.storage.get_ttl(&session_key)?;
+            // session is expired already
+            if session_ttl <= 0 {
+                continue;
+            }
+            session.user_id = to_user_id.to_owned();
+            // moved sessions are not limited, they are active already
+            self.set_session(&session_key, &session, session_ttl as u64, None)
+                .await?;
+            moved_sessions += 1;
+        }
+        self.storage
+            .delete_values(vec![from_user_sessions_key.to_string()])?;
+        Ok(moved_sessions)
+    }
+
     pub async fn remove_sessions_by_keys(
         &mut self,
         session_keys: Vec<String>,
@@ -1,12 +1,16 @@
 #[cfg(test)]
 mod tests {
 
+    use bson::oid::ObjectId;
+
     use crate::{
         app::{
             models::session::Session,
             repositories::session::repository::SessionRepository,
             services::cache::{common::CacheServiceType, service::RedisCacheService},
+            shared::metrics::{SESSION_INDEXES_SCANNED, SESSION_INDEX_SESSIONS_REMOVED},
         },
+        config::session_config::{SessionLimit, SessionLimitPolicy},
         tests::test_data::TestData,
     };
 
@@ -17,7 +21,7 @@ mod tests {
 
         let session_key = Session::get_session_key(&test_data.google_session.id);
         let result = session_repository
-            .set_session(&session_key, &test_data.google_session, 10)
+            .set_session(&session_key, &test_data.google_session, 10, None)
             .await;
         assert_eq!(result.is_ok(), true);
 
@@ -36,7 +40,7 @@ mod tests {
 
         let session_key = Session::get_session_key(&test_data.google_session.id);
         let result = session_repository
-            .set_session(&session_key, &test_data.google_session, 10)
+            .set_session(&session_key, &test_data.google_session, 10, None)
             .await;
         assert_eq!(result.is_ok(), true);
         match session_repository.get_sessions(&session_key).await {
@@ -67,7 +71,7 @@ mod tests {
 
         let session_key = Session::get_session_key(&test_data.google_session.id);
         let result = session_repository
-            .set_session(&session_key, &test_data.google_session, 10)
+            .set_session(&session_key, &test_data.google_session, 10, None)
             .await;
         assert_eq!(result.is_ok(), true);
 
@@ -85,6 +89,277 @@ mod tests {
         }
     }
 
+    #[actix_rt::test]
+    async fn move_sessions() {
+        let (mut session_repository, test_data) = initialize();
+
+        let session_key = Session::get_session_key(&test_data.google_session.id);
+        let result = session_repository
+            .set_session(&session_key, &test_data.google_session, 10, None)
+            .await;
+        assert_eq!(result.is_ok(), true);
+
+        let user_sessions_key = Session::get_user_sessions_key(&test_data.user.id.to_string());
+        let to_user_id = ObjectId::new();
+        let result = session_repository
+            .move_sessions(
+                &user_sessions_key,
+                &to_user_id,
+                vec![test_data.google_session.clone()],
+            )
+            .await;
+        assert_eq!(result.ok(), Some(1));
+
+        let to_user_sessions_key = Session::get_user_sessions_key(&to_user_id.to_string());
+        match session_repository.get_sessions(&to_user_sessions_key).await {
+            Ok(sessions) => {
+                assert_eq!(sessions.len(), 1);
+                if let Some(s) = sessions.into_iter().next() {
+                    assert_eq!(s.user_id, to_user_id);
+                }
+            }
+            Err(err) => assert!(false, "Error to get moved sessions: {}", err),
+        };
+        let result = session_repository.get_sessions(&user_sessions_key).await;
+        assert_eq!(result.map(|sessions| sessions.len()).ok(), Some(0));
+
+        let result = session_repository
+            .remove_sessions(&to_user_sessions_key, vec![session_key.clone()])
+            .await;
+        assert_eq!(result.is_ok(), true);
+    }
+
+    #[actix_rt::test]
+    async fn set_session_with_limit() {
+        let (mut session_repository, test_data) = initialize();
+
+        let user_id = ObjectId::new();
+        let mut old_session = test_data.google_session.clone();
+        old_session.user_id = user_id;
+        let mut new_session = old_session.clone();
+        new_session.id = Session::generate_session_id();
+        let session_limit = SessionLimit {
+            max_sessions: 1,
+            policy: SessionLimitPolicy::EvictLeastRecentlyUsed,
+        };
+
+        let old_session_key = Session::get_session_key(&old_session.id);
+        let result = session_repository
+            .set_session(&old_session_key, &old_session, 10, Some(session_limit))
+            .await;
+        assert_eq!(result.map(|evicted| evicted.len()).ok(), Some(0));
+
+        let new_session_key = Session::get_session_key(&new_session.id);
+        let result = session_repository
+            .set_session(&new_session_key, &new_session, 10, Some(session_limit))
+            .await;
+        match result {
+            Ok(evicted_sessions) => {
+                assert_eq!(evicted_sessions.len(), 1);
+                assert_eq!(evicted_sessions[0].id, old_session.id);
+            }
+            Err(err) => assert!(false, "Error to set session with limit: {}", err),
+        };
+
+        let result = session_repository
+            .set_session(
+                &old_session_key,
+                &old_session,
+                10,
+                Some(SessionLimit {
+                    max_sessions: 1,
+                    policy: SessionLimitPolicy::Reject,
+                }),
+            )
+            .await;
+        assert_eq!(result.is_err(), true);
+
+        let user_sessions_key = Session::get_user_sessions_key(&user_id.to_string());
+        let result = session_repository
+            .remove_all_sessions(&user_sessions_key)
+            .await;
+        assert_eq!(result.is_ok(), true);
+    }
+
+    #[actix_rt::test]
+    async fn get_sessions_removes_expired_ones_from_index() {
+        let (mut session_repository, test_data) = initialize();
+        let redis_cache = RedisCacheService::new(CacheServiceType::Session)
+            .expect("Error to create Session Cache");
+
+        let user_id = ObjectId::new();
+        let (expired_session_key, active_session_key) =
+            set_user_sessions(&mut session_repository, &test_data, user_id).await;
+        let user_sessions_key = Session::get_user_sessions_key(&user_id.to_string());
+        let removed_sessions_before = SESSION_INDEX_SESSIONS_REMOVED.get();
+
+        match session_repository.get_sessions(&user_sessions_key).await {
+            Ok(sessions) => {
+                assert_eq!(sessions.len(), 1);
+                assert_eq!(
+                    Session::get_session_key(&sessions[0].id),
+                    active_session_key
+                );
+            }
+            Err(err) => assert!(false, "Error to get user sessions: {}", err),
+        };
+
+        // the expired session is removed from the index on read
+        match redis_cache.get_all_set_values(&user_sessions_key) {
+            Ok(index) => {
+                assert_eq!(index.len(), 1);
+                assert_eq!(index.contains_key(&expired_session_key), false);
+            }
+            Err(err) => assert!(false, "Error to get user sessions index: {}", err),
+        };
+        assert_eq!(
+            SESSION_INDEX_SESSIONS_REMOVED.get() > removed_sessions_before,
+            true
+        );
+
+        let result = session_repository
+            .remove_all_sessions(&user_sessions_key)
+            .await;
+        assert_eq!(result.is_ok(), true);
+    }
+
+    #[actix_rt::test]
+    async fn cleanup_sessions_indexes() {
+        let (mut session_repository, test_data) = initialize();
+        let redis_cache = RedisCacheService::new(CacheServiceType::Session)
+            .expect("Error to create Session Cache");
+
+        let user_id = ObjectId::new();
+        let (expired_session_key, _) =
+            set_user_sessions(&mut session_repository, &test_data, user_id).await;
+        let user_sessions_key = Session::get_user_sessions_key(&user_id.to_string());
+        let scanned_indexes_before = SESSION_INDEXES_SCANNED.get();
+
+        match session_repository.cleanup_sessions_indexes().await {
+            Ok(report) => {
+                assert_eq!(report.scanned_indexes >= 1, true);
+                assert_eq!(report.removed_sessions >= 1, true);
+            }
+            Err(err) => assert!(false, "Error to cleanup sessions indexes: {}", err),
+        };
+
+        match redis_cache.get_all_set_values(&user_sessions_key) {
+            Ok(index) => {
+                assert_eq!(index.len(), 1);
+                assert_eq!(index.contains_key(&expired_session_key), false);
+            }
+            Err(err) => assert!(false, "Error to get user sessions index: {}", err),
+        };
+        // the index ttl is never shorter than the active session one
+        match redis_cache.get_ttl(&user_sessions_key) {
+            Ok(ttl) => assert_eq!(ttl > 0 && ttl <= 20, true),
+            Err(err) => assert!(false, "Error to get user sessions index ttl: {}", err),
+        };
+        assert_eq!(SESSION_INDEXES_SCANNED.get() > scanned_indexes_before, true);
+
+        let result = session_repository
+            .remove_all_sessions(&user_sessions_key)
+            .await;
+        assert_eq!(result.is_ok(), true);
+    }
+
+    #[actix_rt::test]
+    async fn set_and_remove_session_with_index() {
+        let (mut session_repository, test_data) = initialize();
+        let redis_cache = RedisCacheService::new(CacheServiceType::Session)
+            .expect("Error to create Session Cache");
+
+        let mut session = test_data.google_session.clone();
+        session.user_id = ObjectId::new();
+        let session_key = Session::get_session_key(&session.id);
+        let user_sessions_key = Session::get_user_sessions_key(&session.user_id.to_string());
+
+        // the session and its index field are written together
+        let result = session_repository
+            .set_session(&session_key, &session, 10, None)
+            .await;
+        assert_eq!(result.is_ok(), true);
+        match redis_cache.get_value::<Session>(&session_key) {
+            Ok(stored_session) => assert_eq!(stored_session.is_some(), true),
+            Err(err) => assert!(false, "Error to get session: {}", err),
+        };
+        match redis_cache.get_all_set_values(&user_sessions_key) {
+            Ok(index) => assert_eq!(index.contains_key(&session_key), true),
+            Err(err) => assert!(false, "Error to get user sessions index: {}", err),
+        };
+        match redis_cache.get_ttl(&user_sessions_key) {
+            Ok(ttl) => assert_eq!(ttl > 0 && ttl <= 10, true),
+            Err(err) => assert!(false, "Error to get user sessions index ttl: {}", err),
+        };
+
+        // and deleted together
+        let result = session_repository
+            .remove_sessions(&user_sessions_key, vec![session_key.clone()])
+            .await;
+        assert_eq!(result.is_ok(), true);
+        match redis_cache.get_value::<Session>(&session_key) {
+            Ok(stored_session) => assert_eq!(stored_session.is_none(), true),
+            Err(err) => assert!(false, "Error to get session: {}", err),
+        };
+        match redis_cache.get_all_set_values(&user_sessions_key) {
+            Ok(index) => assert_eq!(index.contains_key(&session_key), false),
+            Err(err) => assert!(false, "Error to get user sessions index: {}", err),
+        };
+    }
+
+    #[actix_rt::test]
+    async fn take_value_once() {
+        let (_, test_data) = initialize();
+        let redis_cache = RedisCacheService::new(CacheServiceType::Session)
+            .expect("Error to create Session Cache");
+
+        let key = Session::get_session_key(&Session::generate_session_id());
+        let result = redis_cache.set_value_with_ttl(&key, &test_data.google_session, 10);
+        assert_eq!(result.is_ok(), true);
+
+        match redis_cache.take_value::<Session>(&key) {
+            Ok(Some(session)) => assert_eq!(session.id, test_data.google_session.id),
+            Ok(None) => assert!(false, "No value to take!?"),
+            Err(err) => assert!(false, "Error to take value: {}", err),
+        };
+        // one-time value is taken already
+        match redis_cache.take_value::<Session>(&key) {
+            Ok(value) => assert_eq!(value.is_none(), true),
+            Err(err) => assert!(false, "Error to take value: {}", err),
+        };
+    }
+
+    // sets two user sessions, the first one is expired(removed from cache but not from the index),
+    // returns session keys of expired and active sessions
+    async fn set_user_sessions(
+        session_repository: &mut SessionRepository,
+        test_data: &TestData,
+        user_id: ObjectId,
+    ) -> (String, String) {
+        let mut expired_session = test_data.google_session.clone();
+        expired_session.user_id = user_id;
+        let mut active_session = expired_session.clone();
+        active_session.id = Session::generate_session_id();
+
+        let expired_session_key = Session::get_session_key(&expired_session.id);
+        let result = session_repository
+            .set_session(&expired_session_key, &expired_session, 10, None)
+            .await;
+        assert_eq!(result.is_ok(), true);
+        let active_session_key = Session::get_session_key(&active_session.id);
+        let result = session_repository
+            .set_session(&active_session_key, &active_session, 20, None)
+            .await;
+        assert_eq!(result.is_ok(), true);
+
+        let result = session_repository
+            .remove_sessions_by_keys(vec![expired_session_key.clone()])
+            .await;
+        assert_eq!(result.is_ok(), true);
+
+        (expired_session_key, active_session_key)
+    }
+
     fn initialize() -> (SessionRepository, TestData) {
         let redis_cache = RedisCacheService::new(CacheServiceType::Session)
             .expect("Error to create Session Cache");
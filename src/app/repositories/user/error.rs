@@ -1,4 +1,5 @@
 use mongodb::bson::ser::Error as MongoDBBsonError;
+use mongodb::error::{ErrorKind, WriteFailure};
 use redis::RedisError;
 use thiserror::Error;
 
@@ -26,6 +27,22 @@ pub enum UserRepositoryError {
 
     #[error("SerdeJson error")]
     SerdeJsonError,
+
+    #[error("Duplicate user error")]
+    Duplicate,
+}
+
+const DUPLICATE_KEY_ERROR_CODE: i32 = 11000;
+
+fn is_duplicate_key_error(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => {
+            write_error.code == DUPLICATE_KEY_ERROR_CODE
+        }
+        // find_one_and_update returns command error
+        ErrorKind::Command(command_error) => command_error.code == DUPLICATE_KEY_ERROR_CODE,
+        _ => false,
+    }
 }
 
 impl From<RedisError> for UserRepositoryError {
@@ -45,6 +62,9 @@ impl From<mongodb::error::Error> for UserRepositoryError {
     fn from(err: mongodb::error::Error) -> Self {
         println!("mongodb::error::Error: {:?}", err);
         log::debug!("mongodb::error::Error: {}", err);
+        if is_duplicate_key_error(&err) {
+            return UserRepositoryError::Duplicate;
+        }
         return UserRepositoryError::MongoDBError;
     }
 }
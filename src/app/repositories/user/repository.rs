@@ -1,17 +1,50 @@
 use bson::oid::ObjectId;
 use bson::Document;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
 use mongodb::bson::{self, doc};
+use mongodb::options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument};
 use mongodb::Collection;
 
-use crate::app::models::{user::User, user_profile::UserProfile};
+use crate::app::models::{
+    common::AuthProviders,
+    login_record::{LoginRecord, LOGIN_HISTORY_SIZE},
+    pending_merge::PendingMerge,
+    role::Role,
+    user::User,
+    user_audit::UserAuditRecord,
+    user_data_export::UserDataExport,
+    user_event::{UserEvent, USER_EVENTS_CHANNEL},
+    user_profile::{CyberSherlockProfileUpdate, UserProfile},
+    user_status::Suspension,
+};
+use crate::app::providers::cyber_sherlock::common::ContactChange;
 use crate::app::services::cache::service::RedisCacheService;
 use crate::app::services::storage::service::StorageService;
+use crate::app::shared::normalization::{normalize_email, normalize_phone};
 use crate::config::user_config::UserConfig;
 
 use super::error::UserRepositoryError;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
+pub struct NormalizationReport {
+    pub processed: usize,
+    pub updated: usize,
+    pub failed: usize,
+}
+
+/// admin users search, all the set fields have to match
+#[derive(Debug, Default)]
+pub struct UserSearch {
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    // system user id or provider(Google, Facebook) user id
+    pub provider_id: Option<String>,
+    // case insensitive part of any profile name
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
 pub struct UserRepository {
     config: UserConfig,
     collection: String,
@@ -49,18 +82,46 @@ impl UserRepository {
         &self,
         email: &str,
     ) -> Result<Option<User>, UserRepositoryError> {
-        let filter = get_find_user_by_email_query(email);
+        let normalized_email = normalize_email(email).unwrap_or(email.to_string());
+        let filter = get_find_user_by_email_query(email, &normalized_email);
         self.find_one(filter).await
     }
 
     pub async fn find_user_by_phone(
         &self,
-        email: &str,
+        phone: &str,
     ) -> Result<Option<User>, UserRepositoryError> {
-        let filter = get_find_user_by_phone_query(email);
+        let normalized_phone =
+            normalize_phone(phone, &self.config.phone_default_region).unwrap_or(phone.to_string());
+        let filter = get_find_user_by_phone_query(phone, &normalized_phone);
         self.find_one(filter).await
     }
 
+    /// one-off backfill to set normalized email and phone on users stored before normalization
+    pub async fn normalize_all_users(
+        &mut self,
+    ) -> Result<NormalizationReport, UserRepositoryError> {
+        let mut report = NormalizationReport::default();
+        let mut cursor = self.get_collection().find(doc! {}, None).await?;
+        while let Some(user) = cursor.try_next().await? {
+            report.processed += 1;
+            let query =
+                get_update_normalized_fields_query(&user, &self.config.phone_default_region);
+            if query.is_empty() {
+                continue;
+            }
+            match self.update_user(&user.id, query).await {
+                Ok(_) => report.updated += 1,
+                Err(err) => {
+                    // normalized values can be duplicated, such users have to be merged by admin
+                    log::error!("Unable to normalize User {}: {}", &user.id, err);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
     pub async fn find_user_by_profile(
         &mut self,
         profile: UserProfile,
@@ -80,7 +141,17 @@ impl UserRepository {
         user_id: &ObjectId,
         data_to_update: Document,
     ) -> Result<User, UserRepositoryError> {
-        let user = self.update_user_in_storage(user_id, data_to_update).await?;
+        self.update_user_by_query(user_id, doc! { "$set": data_to_update })
+            .await
+    }
+
+    // update_query is a complete MongoDB update document(with $set, $unset etc operators)
+    pub async fn update_user_by_query(
+        &mut self,
+        user_id: &ObjectId,
+        update_query: Document,
+    ) -> Result<User, UserRepositoryError> {
+        let user = self.update_user_in_storage(user_id, update_query).await?;
         self.set_user_in_cache(&user)?;
         Ok(user)
     }
@@ -106,6 +177,274 @@ impl UserRepository {
         let user = self.update_user(user_id, query).await?;
         Ok(user)
     }
+
+    /// updates user editable CyberSherlock profile fields, provider profiles are read-only
+    pub async fn update_cyber_sherlock_profile(
+        &mut self,
+        user_id: &ObjectId,
+        profile_update: CyberSherlockProfileUpdate,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_update_cyber_sherlock_profile_query(profile_update);
+        let user = self.update_user(user_id, query).await?;
+        Ok(user)
+    }
+
+    /// sets confirmed email or phone as verified one,
+    /// duplicates are rejected by user storage indexes
+    pub async fn update_cyber_sherlock_contact(
+        &mut self,
+        user_id: &ObjectId,
+        change: ContactChange,
+    ) -> Result<User, UserRepositoryError> {
+        let query =
+            get_update_cyber_sherlock_contact_query(change, &self.config.phone_default_region);
+        let user = self.update_user(user_id, query).await?;
+        Ok(user)
+    }
+
+    /// sets the whole provider profile on the user and keeps the audit record
+    pub async fn link_user_profile(
+        &mut self,
+        user_id: &ObjectId,
+        user_profile: UserProfile,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        self.link_user_profiles(user_id, vec![user_profile], audit_record)
+            .await
+    }
+
+    pub async fn link_user_profiles(
+        &mut self,
+        user_id: &ObjectId,
+        user_profiles: Vec<UserProfile>,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_link_user_profiles_query(user_profiles, audit_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    pub fn invalidate_user_cache(&mut self, user_id: &ObjectId) -> Result<(), UserRepositoryError> {
+        let key = User::get_user_cache_key(user_id.to_string().as_ref());
+        self.cache.delete_values(vec![key])?;
+        Ok(())
+    }
+
+    pub fn set_pending_merge(
+        &self,
+        merge_token: &str,
+        pending_merge: &PendingMerge,
+    ) -> Result<(), UserRepositoryError> {
+        self.cache.set_value_with_ttl(
+            &PendingMerge::get_pending_merge_key(&pending_merge.user_id, merge_token),
+            pending_merge,
+            self.config.merge_confirmation_ttl_sec,
+        )?;
+        Ok(())
+    }
+
+    pub fn take_pending_merge(
+        &self,
+        user_id: &ObjectId,
+        merge_token: &str,
+    ) -> Result<Option<PendingMerge>, UserRepositoryError> {
+        let key = PendingMerge::get_pending_merge_key(user_id, merge_token);
+        // pending merge is deleted on getting to avoid using it again
+        let pending_merge = self.cache.take_value::<PendingMerge>(&key)?;
+        Ok(pending_merge)
+    }
+
+    pub fn set_user_data_export(
+        &self,
+        export_token: &str,
+        user_data_export: &UserDataExport,
+    ) -> Result<(), UserRepositoryError> {
+        self.cache.set_value_with_ttl(
+            &UserDataExport::get_user_data_export_key(export_token),
+            user_data_export,
+            self.config.data_export_link_ttl_sec,
+        )?;
+        Ok(())
+    }
+
+    pub fn take_user_data_export(
+        &self,
+        export_token: &str,
+    ) -> Result<Option<UserDataExport>, UserRepositoryError> {
+        let key = UserDataExport::get_user_data_export_key(export_token);
+        // export link is valid once
+        let user_data_export = self.cache.take_value::<UserDataExport>(&key)?;
+        Ok(user_data_export)
+    }
+
+    pub async fn update_active_profile(
+        &mut self,
+        user_id: &ObjectId,
+        active_profile: &AuthProviders,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_update_active_profile_query(active_profile)?;
+        let user = self.update_user(user_id, query).await?;
+        Ok(user)
+    }
+
+    pub async fn remove_user_profile(
+        &mut self,
+        user_id: &ObjectId,
+        provider: &AuthProviders,
+        active_profile: &AuthProviders,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_remove_user_profile_query(provider, active_profile)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    /// soft deletes the user, the user is purged by purge_at
+    pub async fn schedule_user_deletion(
+        &mut self,
+        user_id: &ObjectId,
+        purge_at: DateTime<Utc>,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_schedule_user_deletion_query(purge_at, audit_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    pub async fn restore_user(
+        &mut self,
+        user_id: &ObjectId,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_restore_user_query(audit_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    pub async fn add_login_record(
+        &mut self,
+        user_id: &ObjectId,
+        login_record: &LoginRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_add_login_record_query(login_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    pub async fn update_user_roles(
+        &mut self,
+        user_id: &ObjectId,
+        roles: &[Role],
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_update_user_roles_query(roles, audit_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    /// no max sessions removes the user own limit, the role limits are used then
+    pub async fn update_user_max_sessions(
+        &mut self,
+        user_id: &ObjectId,
+        max_sessions: Option<u64>,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_update_user_max_sessions_query(max_sessions, audit_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    /// returns the page of found users(the latest created first) and total number of found users
+    pub async fn search_users(
+        &self,
+        search: &UserSearch,
+        skip: u64,
+        limit: i64,
+    ) -> Result<(Vec<User>, u64), UserRepositoryError> {
+        let filter = self.get_search_users_query(search);
+        let total = self
+            .get_collection()
+            .count_documents(filter.clone(), None)
+            .await?;
+        let options = FindOptions::builder()
+            .sort(doc! { "created_at": -1 })
+            .skip(skip)
+            .limit(limit)
+            .build();
+        let cursor = self.get_collection().find(filter, options).await?;
+        let users = cursor.try_collect().await?;
+        Ok((users, total))
+    }
+
+    /// suspended user can't login and use the sessions till the suspension ends
+    pub async fn suspend_user(
+        &mut self,
+        user_id: &ObjectId,
+        suspension: &Suspension,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_suspend_user_query(suspension, audit_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    pub async fn reactivate_user(
+        &mut self,
+        user_id: &ObjectId,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_reactivate_user_query(audit_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    pub async fn update_cyber_sherlock_hash(
+        &mut self,
+        user_id: &ObjectId,
+        hash: &str,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserRepositoryError> {
+        let query = get_update_cyber_sherlock_hash_query(hash, audit_record)?;
+        let user = self.update_user_by_query(user_id, query).await?;
+        Ok(user)
+    }
+
+    pub async fn find_users_to_purge(&self) -> Result<Vec<User>, UserRepositoryError> {
+        let cursor = self
+            .get_collection()
+            .find(get_find_users_to_purge_query(), None)
+            .await?;
+        let users = cursor.try_collect().await?;
+        Ok(users)
+    }
+
+    pub fn publish_user_event(&self, user_event: &UserEvent) -> Result<(), UserRepositoryError> {
+        self.cache.publish(USER_EVENTS_CHANNEL, user_event)?;
+        Ok(())
+    }
+
+    fn get_search_users_query(&self, search: &UserSearch) -> Document {
+        let mut conditions: Vec<Document> = vec![];
+        if let Some(email) = &search.email {
+            let normalized_email = normalize_email(email).unwrap_or(email.to_string());
+            conditions.push(get_find_user_by_email_query(email, &normalized_email));
+        }
+        if let Some(phone) = &search.phone {
+            let normalized_phone = normalize_phone(phone, &self.config.phone_default_region)
+                .unwrap_or(phone.to_string());
+            conditions.push(get_find_user_by_phone_query(phone, &normalized_phone));
+        }
+        if let Some(provider_id) = &search.provider_id {
+            conditions.push(get_find_user_by_provider_id_query(provider_id));
+        }
+        if let Some(name) = &search.name {
+            conditions.push(get_find_user_by_name_query(name));
+        }
+        if conditions.is_empty() {
+            return doc! {};
+        }
+        doc! { "$and": conditions }
+    }
+
     fn get_collection(&self) -> Collection<User> {
         self.storage.get_collection::<User>(&self.collection)
     }
@@ -140,9 +479,7 @@ impl UserRepository {
         &mut self,
         user_id: &ObjectId,
     ) -> Result<(), UserRepositoryError> {
-        let key = User::get_user_cache_key(user_id.to_string().as_ref());
-        self.cache.delete_values(vec![key])?;
-        Ok(())
+        self.invalidate_user_cache(user_id)
     }
 
     async fn insert_user_in_storage(&self, user: &User) -> Result<(), UserRepositoryError> {
@@ -153,16 +490,16 @@ impl UserRepository {
     async fn update_user_in_storage(
         &mut self,
         user_id: &ObjectId,
-        data_to_update: Document,
+        update_query: Document,
     ) -> Result<User, UserRepositoryError> {
         let filter = get_find_user_by_id_query(user_id);
+        // it has to return updated user to keep cache in actual state
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
         if let Some(user) = self
             .get_collection()
-            .find_one_and_update(
-                filter.clone(),
-                doc! { "$set": data_to_update.clone() },
-                None,
-            )
+            .find_one_and_update(filter.clone(), update_query.clone(), options)
             .await?
         {
             Ok(user)
@@ -170,7 +507,7 @@ impl UserRepository {
             log::error!(
                 "Error to update user. filter: {}, data: {}",
                 filter,
-                data_to_update
+                update_query
             );
             Err(UserRepositoryError::UpdateUserError)
         }
@@ -201,6 +538,7 @@ fn get_update_user_profile_query(user_profile: UserProfile) -> Document {
                 "cyber_sherlock.email": cyber_sherlock_profile.email,
                 "cyber_sherlock.email_verified": cyber_sherlock_profile.email_verified,
                 "cyber_sherlock.picture": cyber_sherlock_profile.picture,
+                "cyber_sherlock.normalized_email": cyber_sherlock_profile.normalized_email,
             }
         }
         UserProfile::Google(google_profile) => {
@@ -209,12 +547,14 @@ fn get_update_user_profile_query(user_profile: UserProfile) -> Document {
                 "google.email": google_profile.email,
                 "google.email_verified": google_profile.email_verified,
                 "google.picture": google_profile.picture,
+                "google.normalized_email": google_profile.normalized_email,
             }
         }
         UserProfile::Facebook(facebook_profile) => {
             doc! {
                 "facebook.name": facebook_profile.name,
                 "facebook.email": facebook_profile.email,
+                "facebook.normalized_email": facebook_profile.normalized_email,
             }
         }
     };
@@ -223,6 +563,269 @@ fn get_update_user_profile_query(user_profile: UserProfile) -> Document {
     data_to_update
 }
 
+fn get_update_cyber_sherlock_profile_query(profile_update: CyberSherlockProfileUpdate) -> Document {
+    let mut data_to_update = doc! {};
+    if let Some(name) = profile_update.name {
+        data_to_update.insert("cyber_sherlock.name", name);
+    }
+    if let Some(picture) = profile_update.picture {
+        data_to_update.insert("cyber_sherlock.picture", picture);
+    }
+    if let Some(locale) = profile_update.locale {
+        data_to_update.insert("cyber_sherlock.locale", locale);
+    }
+    if let Some(timezone) = profile_update.timezone {
+        data_to_update.insert("cyber_sherlock.timezone", timezone);
+    }
+    data_to_update.insert("updated_at", Utc::now());
+
+    data_to_update
+}
+
+fn get_update_cyber_sherlock_contact_query(
+    change: ContactChange,
+    phone_default_region: &str,
+) -> Document {
+    let mut data_to_update = match change {
+        ContactChange::Email(email) => doc! {
+            "cyber_sherlock.normalized_email": normalize_email(&email),
+            "cyber_sherlock.email": email,
+            "cyber_sherlock.email_verified": true,
+        },
+        ContactChange::Phone(phone) => doc! {
+            "cyber_sherlock.normalized_phone": normalize_phone(&phone, phone_default_region),
+            "cyber_sherlock.phone": phone,
+            "cyber_sherlock.phone_verified": true,
+        },
+    };
+    data_to_update.insert("updated_at", Utc::now());
+
+    data_to_update
+}
+
+// returns empty document in case user normalized fields are actual
+fn get_update_normalized_fields_query(user: &User, phone_default_region: &str) -> Document {
+    let mut data_to_update = doc! {};
+    if let Some(profile) = &user.cyber_sherlock {
+        let normalized_email = profile.email.as_ref().and_then(|e| normalize_email(e));
+        if normalized_email != profile.normalized_email {
+            data_to_update.insert("cyber_sherlock.normalized_email", normalized_email);
+        }
+        let normalized_phone = profile
+            .phone
+            .as_ref()
+            .and_then(|p| normalize_phone(p, phone_default_region));
+        if normalized_phone != profile.normalized_phone {
+            data_to_update.insert("cyber_sherlock.normalized_phone", normalized_phone);
+        }
+    }
+    if let Some(profile) = &user.google {
+        let normalized_email = normalize_email(&profile.email);
+        if normalized_email != profile.normalized_email {
+            data_to_update.insert("google.normalized_email", normalized_email);
+        }
+    }
+    if let Some(profile) = &user.facebook {
+        let normalized_email = profile.email.as_ref().and_then(|e| normalize_email(e));
+        if normalized_email != profile.normalized_email {
+            data_to_update.insert("facebook.normalized_email", normalized_email);
+        }
+    }
+    data_to_update
+}
+
+fn get_profile_field_name(provider: &AuthProviders) -> &'static str {
+    match provider {
+        AuthProviders::CyberSherlock => "cyber_sherlock",
+        AuthProviders::Google => "google",
+        AuthProviders::Facebook => "facebook",
+    }
+}
+
+fn get_link_user_profiles_query(
+    user_profiles: Vec<UserProfile>,
+    audit_record: UserAuditRecord,
+) -> Result<Document, UserRepositoryError> {
+    let mut data_to_update = doc! {};
+    for user_profile in user_profiles {
+        let provider = UserProfile::get_provider(&user_profile);
+        let profile = match user_profile {
+            UserProfile::CyberSherlock(cyber_sherlock_profile) => {
+                bson::to_bson(&cyber_sherlock_profile)?
+            }
+            UserProfile::Google(google_profile) => bson::to_bson(&google_profile)?,
+            UserProfile::Facebook(facebook_profile) => bson::to_bson(&facebook_profile)?,
+        };
+        data_to_update.insert(get_profile_field_name(&provider), profile);
+    }
+    data_to_update.insert("updated_at", Utc::now());
+    Ok(doc! {
+        "$set": data_to_update,
+        "$push": { "audit_trail": bson::to_bson(&audit_record)? },
+    })
+}
+
+fn get_update_active_profile_query(
+    active_profile: &AuthProviders,
+) -> Result<Document, UserRepositoryError> {
+    Ok(doc! {
+        "active_profile": bson::to_bson(active_profile)?,
+        "updated_at": Utc::now(),
+    })
+}
+
+fn get_remove_user_profile_query(
+    provider: &AuthProviders,
+    active_profile: &AuthProviders,
+) -> Result<Document, UserRepositoryError> {
+    Ok(doc! {
+        "$unset": { get_profile_field_name(provider): "" },
+        "$set": {
+            "active_profile": bson::to_bson(active_profile)?,
+            "updated_at": Utc::now(),
+        },
+    })
+}
+
+fn get_schedule_user_deletion_query(
+    purge_at: DateTime<Utc>,
+    audit_record: UserAuditRecord,
+) -> Result<Document, UserRepositoryError> {
+    let now = Utc::now();
+    Ok(doc! {
+        "$set": {
+            "deleted_at": now,
+            "purge_at": purge_at,
+            "updated_at": now,
+        },
+        "$push": { "audit_trail": bson::to_bson(&audit_record)? },
+    })
+}
+
+fn get_restore_user_query(audit_record: UserAuditRecord) -> Result<Document, UserRepositoryError> {
+    Ok(doc! {
+        "$unset": { "deleted_at": "", "purge_at": "" },
+        "$set": {
+            "updated_at": Utc::now(),
+        },
+        "$push": { "audit_trail": bson::to_bson(&audit_record)? },
+    })
+}
+
+fn get_add_login_record_query(login_record: &LoginRecord) -> Result<Document, UserRepositoryError> {
+    Ok(doc! {
+        "$push": {
+            "login_history": {
+                "$each": [bson::to_bson(login_record)?],
+                "$slice": -LOGIN_HISTORY_SIZE,
+            },
+        },
+    })
+}
+
+fn get_update_user_roles_query(
+    roles: &[Role],
+    audit_record: UserAuditRecord,
+) -> Result<Document, UserRepositoryError> {
+    Ok(doc! {
+        "$set": {
+            "roles": bson::to_bson(roles)?,
+            "updated_at": Utc::now(),
+        },
+        "$push": { "audit_trail": bson::to_bson(&audit_record)? },
+    })
+}
+
+fn get_update_user_max_sessions_query(
+    max_sessions: Option<u64>,
+    audit_record: UserAuditRecord,
+) -> Result<Document, UserRepositoryError> {
+    let audit_record = bson::to_bson(&audit_record)?;
+    Ok(match max_sessions {
+        Some(max_sessions) => doc! {
+            "$set": {
+                "max_sessions": max_sessions as i64,
+                "updated_at": Utc::now(),
+            },
+            "$push": { "audit_trail": audit_record },
+        },
+        None => doc! {
+            "$unset": { "max_sessions": "" },
+            "$set": {
+                "updated_at": Utc::now(),
+            },
+            "$push": { "audit_trail": audit_record },
+        },
+    })
+}
+
+fn get_suspend_user_query(
+    suspension: &Suspension,
+    audit_record: UserAuditRecord,
+) -> Result<Document, UserRepositoryError> {
+    Ok(doc! {
+        "$set": {
+            "suspension": bson::to_bson(suspension)?,
+            "updated_at": Utc::now(),
+        },
+        "$push": { "audit_trail": bson::to_bson(&audit_record)? },
+    })
+}
+
+fn get_reactivate_user_query(
+    audit_record: UserAuditRecord,
+) -> Result<Document, UserRepositoryError> {
+    Ok(doc! {
+        "$unset": { "suspension": "" },
+        "$set": {
+            "updated_at": Utc::now(),
+        },
+        "$push": { "audit_trail": bson::to_bson(&audit_record)? },
+    })
+}
+
+fn get_update_cyber_sherlock_hash_query(
+    hash: &str,
+    audit_record: UserAuditRecord,
+) -> Result<Document, UserRepositoryError> {
+    Ok(doc! {
+        "$set": {
+            "cyber_sherlock.hash": hash,
+            "updated_at": Utc::now(),
+        },
+        "$push": { "audit_trail": bson::to_bson(&audit_record)? },
+    })
+}
+
+fn get_find_user_by_provider_id_query(provider_id: &str) -> Document {
+    let mut conditions = vec![
+        doc! { "google.user_id": provider_id },
+        doc! { "facebook.user_id": provider_id },
+    ];
+    // CyberSherlock profile has system user id
+    if let Ok(user_id) = ObjectId::parse_str(provider_id) {
+        conditions.push(doc! { "_id": user_id });
+    }
+    doc! { "$or": conditions }
+}
+
+fn get_find_user_by_name_query(name: &str) -> Document {
+    let pattern = regex::escape(name);
+    doc! {
+        "$or": [
+            doc! { "cyber_sherlock.name": { "$regex": pattern.as_str(), "$options": "i" } },
+            doc! { "google.name": { "$regex": pattern.as_str(), "$options": "i" } },
+            doc! { "facebook.name": { "$regex": pattern.as_str(), "$options": "i" } },
+        ]
+    }
+}
+
+fn get_find_users_to_purge_query() -> Document {
+    doc! {
+        "purge_at": { "$lte": Utc::now() }
+    }
+}
+
 fn get_find_user_by_profile_query(user_profile: UserProfile) -> Document {
     let mut query = doc! {};
     match user_profile {
@@ -246,9 +849,19 @@ fn get_find_user_by_id_query(user_id: &ObjectId) -> Document {
     }
 }
 
-fn get_find_user_by_email_query(email: &str) -> Document {
+// it keeps original values search for users stored before normalization
+fn get_find_user_by_email_query(email: &str, normalized_email: &str) -> Document {
     doc! {
         "$or": [
+            doc! {
+                "cyber_sherlock.normalized_email": normalized_email
+            },
+            doc! {
+                "google.normalized_email": normalized_email
+            },
+            doc! {
+                "facebook.normalized_email": normalized_email
+            },
             doc! {
                 "cyber_sherlock.email": email
             },
@@ -262,8 +875,15 @@ fn get_find_user_by_email_query(email: &str) -> Document {
     }
 }
 
-fn get_find_user_by_phone_query(phone: &str) -> Document {
+fn get_find_user_by_phone_query(phone: &str, normalized_phone: &str) -> Document {
     doc! {
-        "cyber_sherlock.phone": phone
+        "$or": [
+            doc! {
+                "cyber_sherlock.normalized_phone": normalized_phone
+            },
+            doc! {
+                "cyber_sherlock.phone": phone
+            }
+        ]
     }
 }
@@ -1,9 +1,24 @@
 #[cfg(test)]
 mod tests {
+    use bson::oid::ObjectId;
+    use chrono::{Duration, Utc};
+
     use crate::{
         app::{
-            models::user_profile::UserProfile,
-            repositories::user::repository::UserRepository,
+            models::{
+                common::AuthProviders,
+                role::Role,
+                user::User,
+                user_audit::{UserAuditEvent, UserAuditRecord},
+                user_profile::{
+                    CyberSherlockProfile, CyberSherlockProfileUpdate, FacebookProfile, UserProfile,
+                },
+                user_status::{Suspension, UserStatus},
+            },
+            repositories::user::{
+                error::UserRepositoryError,
+                repository::{UserRepository, UserSearch},
+            },
             services::{
                 cache::{common::CacheServiceType, service::RedisCacheService},
                 storage::service::StorageService,
@@ -86,6 +101,519 @@ mod tests {
             Err(err) => assert!(false, "{}", err.to_string()),
         }
     }
+    #[actix_rt::test]
+    async fn remove_user_profile() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        // Seed user with Google and Facebook profiles
+        let mut user = test_data.user.clone();
+        user.facebook = Some(FacebookProfile {
+            user_id: format!("fake_facebook_user_id_{}", user.id),
+            name: String::from("fake_facebook_user_name"),
+            email: None,
+            picture: None,
+            normalized_email: None,
+        });
+        let result = user_repository.insert_user(&user).await;
+        assert_eq!(result.is_ok(), true);
+
+        match user_repository
+            .remove_user_profile(&user.id, &AuthProviders::Google, &AuthProviders::Facebook)
+            .await
+        {
+            Ok(updated_user) => {
+                assert_eq!(updated_user.google.is_none(), true);
+                assert_eq!(updated_user.facebook.is_some(), true);
+                assert_eq!(
+                    updated_user
+                        .active_profile
+                        .is_equal(&AuthProviders::Facebook),
+                    true
+                );
+            }
+            Err(err) => assert!(false, "Error remove_user_profile {}", err),
+        };
+
+        let result = user_repository.delete_by_id(&user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn update_active_profile() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        let result = user_repository.insert_user(&test_data.user).await;
+        assert_eq!(result.is_ok(), true);
+
+        let result = user_repository
+            .update_active_profile(&test_data.user.id, &AuthProviders::CyberSherlock)
+            .await;
+        assert_eq!(result.is_ok(), true);
+
+        // updated user has to be in cache
+        match user_repository.find_user_by_id(&test_data.user.id).await {
+            Ok(Some(user)) => assert_eq!(
+                user.active_profile.is_equal(&AuthProviders::CyberSherlock),
+                true
+            ),
+            Ok(None) => assert!(false, "User not found by find_user_by_id!?"),
+            Err(err) => assert!(false, "{}", err.to_string()),
+        }
+
+        let result = user_repository.delete_by_id(&test_data.user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn update_user_roles() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        let result = user_repository.insert_user(&test_data.user).await;
+        assert_eq!(result.is_ok(), true);
+
+        match user_repository
+            .update_user_roles(
+                &test_data.user.id,
+                &[Role::Admin],
+                UserAuditRecord::new(UserAuditEvent::RolesChanged, AuthProviders::Google, None),
+            )
+            .await
+        {
+            Ok(user) => assert_eq!(user.roles, vec![Role::Admin]),
+            Err(err) => assert!(false, "Error update_user_roles {}", err),
+        };
+
+        // updated user roles have to be in cache
+        match user_repository.find_user_by_id(&test_data.user.id).await {
+            Ok(Some(user)) => {
+                assert_eq!(user.has_permission("roles:assign"), true);
+                assert_eq!(user.audit_trail.len(), 1);
+            }
+            Ok(None) => assert!(false, "User not found by find_user_by_id!?"),
+            Err(err) => assert!(false, "{}", err.to_string()),
+        }
+
+        let result = user_repository.delete_by_id(&test_data.user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn update_user_max_sessions() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        let result = user_repository.insert_user(&test_data.user).await;
+        assert_eq!(result.is_ok(), true);
+
+        let audit_record = || {
+            UserAuditRecord::new(
+                UserAuditEvent::MaxSessionsChanged,
+                AuthProviders::Google,
+                None,
+            )
+        };
+        match user_repository
+            .update_user_max_sessions(&test_data.user.id, Some(5), audit_record())
+            .await
+        {
+            Ok(user) => assert_eq!(user.max_sessions, Some(5)),
+            Err(err) => assert!(false, "Error update_user_max_sessions {}", err),
+        };
+
+        // removed limit has to be in cache and storage
+        match user_repository
+            .update_user_max_sessions(&test_data.user.id, None, audit_record())
+            .await
+        {
+            Ok(user) => assert_eq!(user.max_sessions, None),
+            Err(err) => assert!(false, "Error update_user_max_sessions {}", err),
+        };
+        match user_repository.find_user_by_id(&test_data.user.id).await {
+            Ok(Some(user)) => {
+                assert_eq!(user.max_sessions, None);
+                assert_eq!(user.audit_trail.len(), 2);
+            }
+            Ok(None) => assert!(false, "User not found by find_user_by_id!?"),
+            Err(err) => assert!(false, "{}", err.to_string()),
+        }
+
+        let result = user_repository.delete_by_id(&test_data.user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn update_cyber_sherlock_profile() {
+        // user repository and test data
+        let (mut user_repository, _) = intialize().await;
+
+        let user_id = ObjectId::new();
+        let user = User::new(UserProfile::CyberSherlock(CyberSherlockProfile {
+            user_id,
+            name: String::from(""),
+            email: Some(format!("fake_cyber_sherlock_{}@gmail.com", user_id)),
+            email_verified: false,
+            phone: None,
+            phone_verified: false,
+            picture: None,
+            hash: String::from("fake_cyber_sherlock_hash"),
+            normalized_email: None,
+            normalized_phone: None,
+            locale: None,
+            timezone: None,
+        }));
+        let result = user_repository.insert_user(&user).await;
+        assert_eq!(result.is_ok(), true);
+
+        match user_repository
+            .update_cyber_sherlock_profile(
+                &user.id,
+                CyberSherlockProfileUpdate {
+                    name: Some(String::from("Sherlock")),
+                    picture: None,
+                    locale: Some(String::from("en-GB")),
+                    timezone: None,
+                },
+            )
+            .await
+        {
+            Ok(user) => match user.cyber_sherlock {
+                Some(profile) => {
+                    assert_eq!(profile.name, "Sherlock");
+                    assert_eq!(profile.locale, Some(String::from("en-GB")));
+                    // not set fields stay untouched
+                    assert_eq!(profile.timezone, None);
+                    assert_eq!(profile.hash, "fake_cyber_sherlock_hash");
+                }
+                None => assert!(false, "No CyberSherlock profile after update!?"),
+            },
+            Err(err) => assert!(false, "Error update_cyber_sherlock_profile {}", err),
+        };
+
+        // updated profile has to be in cache
+        match user_repository.find_user_by_id(&user.id).await {
+            Ok(Some(user)) => assert_eq!(
+                user.cyber_sherlock.map(|profile| profile.name),
+                Some(String::from("Sherlock"))
+            ),
+            Ok(None) => assert!(false, "User not found by find_user_by_id!?"),
+            Err(err) => assert!(false, "{}", err.to_string()),
+        }
+
+        let result = user_repository.delete_by_id(&user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn suspend_and_reactivate_user() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        let result = user_repository.insert_user(&test_data.user).await;
+        assert_eq!(result.is_ok(), true);
+
+        let suspension = Suspension::new(
+            "spam".to_string(),
+            Some(Utc::now() + Duration::days(1)),
+            ObjectId::new(),
+        );
+        match user_repository
+            .suspend_user(
+                &test_data.user.id,
+                &suspension,
+                UserAuditRecord::new(
+                    UserAuditEvent::AccountSuspended,
+                    AuthProviders::Google,
+                    None,
+                ),
+            )
+            .await
+        {
+            Ok(user) => {
+                assert_eq!(user.get_status(), UserStatus::Suspended);
+                assert_eq!(user.suspension.is_some(), true);
+            }
+            Err(err) => assert!(false, "Error suspend_user {}", err),
+        };
+
+        match user_repository
+            .reactivate_user(
+                &test_data.user.id,
+                UserAuditRecord::new(
+                    UserAuditEvent::AccountReactivated,
+                    AuthProviders::Google,
+                    None,
+                ),
+            )
+            .await
+        {
+            Ok(user) => assert_eq!(user.suspension.is_none(), true),
+            Err(err) => assert!(false, "Error reactivate_user {}", err),
+        };
+
+        // reactivated user has to be in cache with both audit records
+        match user_repository.find_user_by_id(&test_data.user.id).await {
+            Ok(Some(user)) => {
+                assert_eq!(user.get_status(), UserStatus::Active);
+                assert_eq!(user.audit_trail.len(), 2);
+            }
+            Ok(None) => assert!(false, "User not found by find_user_by_id!?"),
+            Err(err) => assert!(false, "{}", err.to_string()),
+        }
+
+        let result = user_repository.delete_by_id(&test_data.user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn schedule_user_deletion_and_restore() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        let result = user_repository.insert_user(&test_data.user).await;
+        assert_eq!(result.is_ok(), true);
+
+        match user_repository
+            .schedule_user_deletion(
+                &test_data.user.id,
+                Utc::now() + Duration::seconds(60),
+                UserAuditRecord::new(
+                    UserAuditEvent::AccountDeletionScheduled,
+                    AuthProviders::Google,
+                    None,
+                ),
+            )
+            .await
+        {
+            Ok(user) => {
+                assert_eq!(user.is_deletion_scheduled(), true);
+                assert_eq!(user.purge_at.is_some(), true);
+                assert_eq!(user.get_status(), UserStatus::PendingDeletion);
+            }
+            Err(err) => assert!(false, "Error schedule_user_deletion {}", err),
+        };
+
+        match user_repository
+            .restore_user(
+                &test_data.user.id,
+                UserAuditRecord::new(UserAuditEvent::AccountRestored, AuthProviders::Google, None),
+            )
+            .await
+        {
+            Ok(user) => {
+                assert_eq!(user.is_deletion_scheduled(), false);
+                assert_eq!(user.purge_at.is_none(), true);
+                assert_eq!(user.get_status(), UserStatus::Active);
+            }
+            Err(err) => assert!(false, "Error restore_user {}", err),
+        };
+
+        let result = user_repository.delete_by_id(&test_data.user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn link_user_profile() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        let result = user_repository.insert_user(&test_data.user).await;
+        assert_eq!(result.is_ok(), true);
+
+        let facebook_profile = FacebookProfile {
+            user_id: format!("fake_facebook_user_id_{}", test_data.user.id),
+            name: String::from("fake_facebook_user_name"),
+            email: None,
+            picture: None,
+            normalized_email: None,
+        };
+        match user_repository
+            .link_user_profile(
+                &test_data.user.id,
+                UserProfile::Facebook(facebook_profile.clone()),
+                UserAuditRecord::new(UserAuditEvent::ProfileMerged, AuthProviders::Facebook, None),
+            )
+            .await
+        {
+            Ok(user) => {
+                assert_eq!(user.google.is_some(), true);
+                assert_eq!(
+                    user.facebook.map(|profile| profile.user_id),
+                    Some(facebook_profile.user_id)
+                );
+                assert_eq!(user.audit_trail.len(), 1);
+            }
+            Err(err) => assert!(false, "Error link_user_profile {}", err),
+        };
+
+        let result = user_repository.delete_by_id(&test_data.user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn find_user_by_normalized_email() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        let google_profile = test_data
+            .user
+            .google
+            .clone()
+            .expect("Bad test user google profile!");
+        let mut user = User::new(UserProfile::normalize(
+            UserProfile::Google(google_profile.clone()),
+            "US",
+        ));
+        user.id = test_data.user.id;
+        let result = user_repository.insert_user(&user).await;
+        assert_eq!(result.is_ok(), true);
+
+        match user_repository
+            .find_user_by_email(&google_profile.email.to_uppercase())
+            .await
+        {
+            Ok(Some(found_user)) => assert_eq!(found_user.id, user.id),
+            Ok(None) => assert!(false, "User not found by normalized email!?"),
+            Err(err) => assert!(false, "Error find_user_by_email {}", err),
+        };
+
+        let result = user_repository.delete_by_id(&user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn insert_duplicate_user() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        let result = user_repository.insert_user(&test_data.user).await;
+        assert_eq!(result.is_ok(), true);
+
+        // another user with the same Google profile
+        let duplicate_user = User::new(UserProfile::Google(
+            test_data
+                .user
+                .google
+                .clone()
+                .expect("Bad test user google profile!"),
+        ));
+        match user_repository.insert_user(&duplicate_user).await {
+            Err(UserRepositoryError::Duplicate) => {}
+            Err(err) => assert!(false, "Unexpected error {}", err),
+            Ok(_) => assert!(false, "Duplicate user inserted!?"),
+        };
+
+        let result = user_repository.delete_by_id(&test_data.user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn search_users() {
+        // user repository and test data
+        let (mut user_repository, test_data) = intialize().await;
+
+        // uniq name part to find the test users only
+        let marker = ObjectId::new().to_string();
+        let google_profile = test_data
+            .user
+            .google
+            .clone()
+            .expect("Bad test user google profile!");
+        let mut users: Vec<User> = vec![];
+        for index in 0..2 {
+            let mut profile = google_profile.clone();
+            profile.user_id = format!("{}_{}", marker, index);
+            profile.email = format!("fake_search_{}_{}@gmail.com", marker, index);
+            profile.name = format!("Sherlock {} {}", marker, index);
+            let mut user = User::new(UserProfile::Google(profile));
+            // the latest created first
+            user.created_at = Utc::now() + Duration::seconds(index);
+            let result = user_repository.insert_user(&user).await;
+            assert_eq!(result.is_ok(), true);
+            users.push(user);
+        }
+
+        let search = UserSearch {
+            name: Some(marker.to_uppercase()),
+            ..Default::default()
+        };
+        match user_repository.search_users(&search, 0, 1).await {
+            Ok((found_users, total)) => {
+                assert_eq!(total, 2);
+                assert_eq!(found_users.len(), 1);
+                assert_eq!(found_users[0].id, users[1].id);
+            }
+            Err(err) => assert!(false, "Error search_users {}", err),
+        };
+        match user_repository.search_users(&search, 1, 1).await {
+            Ok((found_users, total)) => {
+                assert_eq!(total, 2);
+                assert_eq!(found_users.len(), 1);
+                assert_eq!(found_users[0].id, users[0].id);
+            }
+            Err(err) => assert!(false, "Error search_users {}", err),
+        };
+
+        let search = UserSearch {
+            email: Some(format!("fake_search_{}_0@gmail.com", marker)),
+            name: Some(marker.clone()),
+            ..Default::default()
+        };
+        match user_repository.search_users(&search, 0, 10).await {
+            Ok((found_users, total)) => {
+                assert_eq!(total, 1);
+                assert_eq!(found_users[0].id, users[0].id);
+            }
+            Err(err) => assert!(false, "Error search_users {}", err),
+        };
+
+        let search = UserSearch {
+            provider_id: Some(format!("{}_1", marker)),
+            ..Default::default()
+        };
+        match user_repository.search_users(&search, 0, 10).await {
+            Ok((found_users, total)) => {
+                assert_eq!(total, 1);
+                assert_eq!(found_users[0].id, users[1].id);
+            }
+            Err(err) => assert!(false, "Error search_users {}", err),
+        };
+
+        // system user id is the provider id of CyberSherlock profile
+        let search = UserSearch {
+            provider_id: Some(users[0].id.to_string()),
+            ..Default::default()
+        };
+        match user_repository.search_users(&search, 0, 10).await {
+            Ok((found_users, total)) => {
+                assert_eq!(total, 1);
+                assert_eq!(found_users[0].id, users[0].id);
+            }
+            Err(err) => assert!(false, "Error search_users {}", err),
+        };
+
+        // all the search fields have to match
+        let search = UserSearch {
+            email: Some(format!("fake_search_{}_0@gmail.com", marker)),
+            provider_id: Some(format!("{}_1", marker)),
+            ..Default::default()
+        };
+        match user_repository.search_users(&search, 0, 10).await {
+            Ok((found_users, total)) => {
+                assert_eq!(total, 0);
+                assert_eq!(found_users.is_empty(), true);
+            }
+            Err(err) => assert!(false, "Error search_users {}", err),
+        };
+
+        for user in users {
+            let result = user_repository.delete_by_id(&user.id).await;
+            assert_eq!(result.is_ok(), true)
+        }
+    }
+
     async fn intialize() -> (UserRepository, TestData) {
         let user_config = UserConfig::new();
         // Storage service
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use anyhow::Result;
 
-use redis::{Client, Cmd, Connection};
+use redis::{pipe, Client, Cmd, Connection};
 use redis::{Commands, FromRedisValue, ToRedisArgs};
 use serde::Serialize;
 
@@ -41,12 +41,6 @@ impl RedisCacheService {
         serde_json::to_string::<T>(data)
     }
 
-    pub fn set(&self, key: &str, items: (&str, String)) -> Result<(), CacheServiceError> {
-        let mut connection = self.get_connection()?;
-        connection.hset(key, items.0, items.1)?;
-        Ok(())
-    }
-
     pub fn get_all_set_values(
         &self,
         key: &str,
@@ -67,27 +61,22 @@ impl RedisCacheService {
         Ok(())
     }
 
-    pub fn delete_set_values(
-        &self,
-        hset_key: &str,
-        keys: Vec<String>,
-    ) -> Result<(), CacheServiceError> {
+    pub fn get_value<T>(&self, key: &str) -> Result<Option<T>, CacheServiceError>
+    where
+        T: FromRedisValue,
+    {
         let mut connection = self.get_connection()?;
-        let mut cmd = Cmd::new();
-        cmd.arg("HDEL").arg(hset_key);
-        for key in keys {
-            cmd.arg(&key);
-        }
-        cmd.query(&mut connection)?;
-        Ok(())
+        let value: Option<T> = connection.get(key)?;
+        Ok(value)
     }
 
-    pub fn get_value<T>(&self, key: &str) -> Result<Option<T>, CacheServiceError>
+    /// gets and deletes the value in one step(GETDEL), so one-time value can't be taken twice
+    pub fn take_value<T>(&self, key: &str) -> Result<Option<T>, CacheServiceError>
     where
         T: FromRedisValue,
     {
         let mut connection = self.get_connection()?;
-        let value: Option<T> = connection.get(key)?;
+        let value: Option<T> = connection.get_del(key)?;
         Ok(value)
     }
 
@@ -100,6 +89,69 @@ impl RedisCacheService {
         Ok(values)
     }
 
+    // returns key ttl in seconds, negative value means key has no ttl(-1) or doesn't exist(-2)
+    pub fn get_ttl(&self, key: &str) -> Result<i64, CacheServiceError> {
+        let mut connection = self.get_connection()?;
+        let ttl: i64 = connection.ttl(key)?;
+        Ok(ttl)
+    }
+
+    // returns keys ttl in seconds in the same order, see get_ttl
+    pub fn get_ttls(&self, keys: &[String]) -> Result<Vec<i64>, CacheServiceError> {
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut connection = self.get_connection()?;
+        let mut pipeline = pipe();
+        for key in keys {
+            pipeline.ttl(key);
+        }
+        let ttls: Vec<i64> = pipeline.query(&mut connection)?;
+        Ok(ttls)
+    }
+
+    /// sets ttl to the key without ttl or extends the shorter one, it never shortens the ttl
+    /// extended by concurrent writes(Redis 7.0+)
+    pub fn extend_ttl(&self, key: &str, seconds: u64) -> Result<(), CacheServiceError> {
+        let mut connection = self.get_connection()?;
+        pipe()
+            .atomic()
+            .cmd("EXPIRE")
+            .arg(key)
+            .arg(seconds)
+            .arg("NX")
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(key)
+            .arg(seconds)
+            .arg("GT")
+            .ignore()
+            .query::<()>(&mut connection)?;
+        Ok(())
+    }
+
+    /// increments the counter, ttl is set on the counter creation only
+    pub fn increment_with_ttl(&self, key: &str, seconds: u64) -> Result<u64, CacheServiceError> {
+        let mut connection = self.get_connection()?;
+        let (value,): (u64,) = pipe()
+            .atomic()
+            .incr(key, 1)
+            .cmd("EXPIRE")
+            .arg(key)
+            .arg(seconds)
+            .arg("NX")
+            .ignore()
+            .query(&mut connection)?;
+        Ok(value)
+    }
+
+    /// iterates keys by pattern with SCAN to not block Redis as KEYS does
+    pub fn scan_keys(&self, pattern: &str) -> Result<Vec<String>, CacheServiceError> {
+        let mut connection = self.get_connection()?;
+        let keys: Vec<String> = connection.scan_match(pattern)?.collect();
+        Ok(keys)
+    }
+
     // means that data argument impl its own ToRedisArgs logic
     pub fn set_data_with_ttl<T>(
         &self,
@@ -115,6 +167,58 @@ impl RedisCacheService {
         Ok(())
     }
 
+    /// sets data with ttl and the hset item in one transaction(MULTI/EXEC),
+    /// hset ttl is extended to the data ttl to not expire before its items
+    pub fn set_data_with_ttl_and_set_item<T>(
+        &self,
+        key: &str,
+        data: T,
+        seconds: u64,
+        hset_key: &str,
+        items: (&str, String),
+    ) -> Result<(), CacheServiceError>
+    where
+        T: ToRedisArgs,
+    {
+        let mut connection = self.get_connection()?;
+        pipe()
+            .atomic()
+            .set_ex(key, data, seconds)
+            .ignore()
+            .hset(hset_key, items.0, items.1)
+            .ignore()
+            // NX sets ttl to hset without ttl, GT extends the shorter one(Redis 7.0+)
+            .cmd("EXPIRE")
+            .arg(hset_key)
+            .arg(seconds)
+            .arg("NX")
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(hset_key)
+            .arg(seconds)
+            .arg("GT")
+            .ignore()
+            .query::<()>(&mut connection)?;
+        Ok(())
+    }
+
+    /// deletes the keys and the same hset fields in one transaction(MULTI/EXEC)
+    pub fn delete_values_and_set_values(
+        &self,
+        hset_key: &str,
+        keys: Vec<String>,
+    ) -> Result<(), CacheServiceError> {
+        let mut connection = self.get_connection()?;
+        pipe()
+            .atomic()
+            .del(&keys)
+            .ignore()
+            .hdel(hset_key, &keys)
+            .ignore()
+            .query::<()>(&mut connection)?;
+        Ok(())
+    }
+
     pub fn set_value_with_ttl<T>(
         &self,
         key: &str,
@@ -129,6 +233,16 @@ impl RedisCacheService {
         let _: () = connection.set_ex(key, value, seconds)?;
         Ok(())
     }
+
+    pub fn publish<T>(&self, channel: &str, message: &T) -> Result<(), CacheServiceError>
+    where
+        T: Serialize,
+    {
+        let mut connection = self.get_connection()?;
+        let value = serde_json::to_string::<T>(message)?;
+        let _: i64 = connection.publish(channel, value)?;
+        Ok(())
+    }
 }
 
 impl SessionStorage for RedisCacheService {
@@ -136,8 +250,46 @@ impl SessionStorage for RedisCacheService {
         self.get_value::<Session>(&Session::get_session_key(key).as_ref())
     }
 
+    fn load_user_sessions(&self, user_id: &str) -> Result<Vec<Session>, CacheServiceError> {
+        let session_keys: Vec<String> = self
+            .get_all_set_values(&Session::get_user_sessions_key(user_id))?
+            .into_keys()
+            .collect();
+        if session_keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let sessions = self.get_values::<Session>(session_keys)?;
+        Ok(sessions.into_iter().flatten().collect())
+    }
+
     fn set(&self, session: &Session, ttl: u64) -> Result<(), CacheServiceError> {
         let session_key = Session::get_session_key(&session.id);
-        self.set_value_with_ttl(&session_key, session, ttl)
+        // anonymous sessions are not in user sessions set
+        if session.is_anonymous() {
+            return self.set_value_with_ttl(&session_key, session, ttl);
+        }
+        self.set_data_with_ttl_and_set_item::<&Session>(
+            &session_key,
+            session,
+            ttl,
+            &Session::get_user_sessions_key(&session.user_id.to_string()),
+            (&session_key, session.auth_provider.to_string()),
+        )
+    }
+
+    fn remove(&self, session: &Session) -> Result<(), CacheServiceError> {
+        let session_key = Session::get_session_key(&session.id);
+        let user_sessions_key = Session::get_user_sessions_key(&session.user_id.to_string());
+        self.delete_values_and_set_values(&user_sessions_key, vec![session_key])
+    }
+
+    fn count_anonymous_session(&self, client_ip: &str, ttl: u64) -> Result<u64, CacheServiceError> {
+        self.increment_with_ttl(&Session::get_anonymous_sessions_counter_key(client_ip), ttl)
+    }
+
+    fn get_anonymous_sessions_count(&self, client_ip: &str) -> Result<u64, CacheServiceError> {
+        let sessions =
+            self.get_value::<u64>(&Session::get_anonymous_sessions_counter_key(client_ip))?;
+        Ok(sessions.unwrap_or(0))
     }
 }
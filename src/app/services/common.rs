@@ -24,10 +24,53 @@ pub fn error_as_json(error: &str) -> Value {
     json!({ "error": error })
 }
 
+/// return error string with machine-readable error code as json object
+///
+/// #### Response example:
+/// ```
+///  {
+///   "error": "User account is suspended",
+///   "code": "account_suspended"
+///   }
+/// ```
+pub fn error_with_code_as_json(error: &str, code: &str) -> Value {
+    json!({ "error": error, "code": code })
+}
+
 pub fn result_as_json(result: &str) -> Value {
     json!({ "result": result })
 }
 
+/// session token for the clients without cookies, it's sent in `Authorization: Bearer` header
+///
+/// #### Response example:
+/// ```
+///  {
+///   "result": "success",
+///   "session_token": "Some session token",
+///   "token_type": "Bearer",
+///   "expires_in": 86400,
+///   "access_token": "Some CyberSherlock access JWT"
+///   }
+/// ```
+pub fn session_token_as_json(
+    result: &str,
+    session_token: &str,
+    expires_in: u64,
+    access_token: Option<&str>,
+) -> Value {
+    let mut json_session_token = json!({
+        "result": result,
+        "session_token": session_token,
+        "token_type": "Bearer",
+        "expires_in": expires_in,
+    });
+    if let Some(access_token) = access_token {
+        json_session_token["access_token"] = json!(access_token);
+    }
+    json_session_token
+}
+
 pub fn get_x_www_form_headers() -> HeaderMap {
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -41,6 +84,10 @@ pub fn auth_url_as_json(auth_url: &str) -> Value {
     json!({"authorization_url": auth_url})
 }
 
+pub fn merge_token_as_json(error: &str, merge_token: &str) -> Value {
+    json!({ "error": error, "merge_token": merge_token })
+}
+
 pub trait AsyncFn: Send {
     fn handle(
         &mut self,
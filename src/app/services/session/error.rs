@@ -9,11 +9,20 @@ pub enum SessionServiceError {
 
     #[error("SetCookieToResponse error")]
     SetCookieToResponseError,
+
+    #[error("ExpiredTokens error")]
+    ExpiredTokensError,
+
+    #[error("SessionLimitExceeded error")]
+    SessionLimitExceededError,
 }
 
 impl From<SessionRepositoryError> for SessionServiceError {
     fn from(err: SessionRepositoryError) -> Self {
         log::debug!("SessionRepositoryError: {:?}", err);
+        if let SessionRepositoryError::SessionLimitExceeded = err {
+            return SessionServiceError::SessionLimitExceededError;
+        }
         return SessionServiceError::SessionRepositoryError;
     }
 }
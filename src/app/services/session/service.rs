@@ -1,9 +1,10 @@
 use actix_web::{
     cookie::{Cookie, CookieJar},
     dev::{ResponseHead, ServiceRequest},
-    http::header::{HeaderValue, SET_COOKIE},
+    http::header::{HeaderValue, AUTHORIZATION, SET_COOKIE},
 };
 use bson::oid::ObjectId;
+use chrono::{Duration, Utc};
 
 use crate::{
     app::{
@@ -11,7 +12,7 @@ use crate::{
             common::AuthProviders,
             session::{NewSessionData, Session},
         },
-        repositories::session::repository::SessionRepository,
+        repositories::session::repository::{SessionIndexCleanupReport, SessionRepository},
         services::cache::service::RedisCacheService,
     },
     config::{
@@ -48,29 +49,105 @@ impl SessionService {
         Ok(user_sessions)
     }
 
-    // TODO: token in most cases has expiration time
-    // - set session ttl as token expiration
+    pub async fn get_all_sessions(
+        &mut self,
+        user_id: ObjectId,
+    ) -> Result<Vec<Session>, SessionServiceError> {
+        let sessions_key = Session::get_user_sessions_key(&user_id.to_string());
+        let sessions = self.repository.get_sessions(sessions_key.as_ref()).await?;
+        Ok(sessions)
+    }
+
+    pub async fn move_sessions(
+        &mut self,
+        from_user_id: ObjectId,
+        to_user_id: ObjectId,
+        sessions: Vec<Session>,
+    ) -> Result<usize, SessionServiceError> {
+        let from_user_sessions_key = Session::get_user_sessions_key(&from_user_id.to_string());
+        let moved_sessions = self
+            .repository
+            .move_sessions(&from_user_sessions_key, &to_user_id, sessions)
+            .await?;
+        Ok(moved_sessions)
+    }
+
+    pub async fn remove_user_sessions(
+        &mut self,
+        user_id: ObjectId,
+        sessions: Vec<Session>,
+    ) -> Result<(), SessionServiceError> {
+        let user_sessions_key = Session::get_user_sessions_key(&user_id.to_string());
+        let session_keys_to_remove: Vec<String> = sessions
+            .into_iter()
+            .map(|s| Session::get_session_key(&s.id))
+            .collect();
+        self.repository
+            .remove_sessions(&user_sessions_key, session_keys_to_remove)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_other_user_sessions(
+        &mut self,
+        user_id: ObjectId,
+        current_session_id: &str,
+    ) -> Result<usize, SessionServiceError> {
+        let user_sessions_key = Session::get_user_sessions_key(&user_id.to_string());
+        let removed_sessions = self
+            .repository
+            .remove_sessions_except(
+                &user_sessions_key,
+                &Session::get_session_key(current_session_id),
+            )
+            .await?;
+        Ok(removed_sessions)
+    }
+
+    pub async fn remove_all_user_sessions(
+        &mut self,
+        user_id: ObjectId,
+    ) -> Result<(), SessionServiceError> {
+        let user_sessions_key = Session::get_user_sessions_key(&user_id.to_string());
+        self.repository
+            .remove_all_sessions(&user_sessions_key)
+            .await?;
+        Ok(())
+    }
+
+    /// returns the new session and user sessions evicted by the session limit
     pub async fn set_new_session(
         &mut self,
         new_session_data: NewSessionData,
-    ) -> Result<Session, SessionServiceError> {
+        user_max_sessions: Option<u64>,
+        user_roles: &[String],
+    ) -> Result<(Session, Vec<Session>), SessionServiceError> {
         let session = Session::new(new_session_data);
         let session_key = Session::get_session_key(&session.id);
-        self.repository
-            .set_session(&session_key, &session, self.config.session_ttl_sec)
+        let session_ttl = SessionService::get_session_ttl(&self.config, &session)
+            .ok_or(SessionServiceError::ExpiredTokensError)?;
+        let evicted_sessions = self
+            .repository
+            .set_session(
+                &session_key,
+                &session,
+                session_ttl,
+                self.config.get_session_limit(user_max_sessions, user_roles),
+            )
             .await?;
-        Ok(session)
+        Ok((session, evicted_sessions))
     }
 
-    pub async fn remove_sessions_by_session(
+    pub async fn remove_sessions_by_provider(
         &mut self,
-        session: Session,
+        user_id: ObjectId,
+        auth_provider: AuthProviders,
     ) -> Result<(), SessionServiceError> {
-        let user_sessions_key = Session::get_user_sessions_key(&session.user_id.to_string());
+        let user_sessions_key = Session::get_user_sessions_key(&user_id.to_string());
         let user_sessions = self.repository.get_sessions(&user_sessions_key).await?;
         let session_keys_to_remove: Vec<String> = user_sessions
             .into_iter()
-            .filter(|s| s.auth_provider.is_equal(&session.auth_provider))
+            .filter(|s| s.auth_provider.is_equal(&auth_provider))
             .map(|s| Session::get_session_key(&s.id))
             .collect();
         self.repository
@@ -94,6 +171,64 @@ impl SessionService {
         Ok(())
     }
 
+    /// removes the sessions the client had before the privilege change,
+    /// so the session id known before the change can't be used after it
+    pub async fn remove_rotated_sessions(
+        &mut self,
+        new_session: &Session,
+        previous_sessions: Vec<Session>,
+    ) -> Result<(), SessionServiceError> {
+        let (anonymous_sessions, user_sessions): (Vec<Session>, Vec<Session>) = previous_sessions
+            .into_iter()
+            .filter(|s| s.id != new_session.id)
+            .partition(|s| s.is_anonymous());
+        // anonymous sessions are not in user sessions index
+        self.remove_sessions(anonymous_sessions).await?;
+        for session in user_sessions {
+            self.remove_user_sessions(session.user_id, vec![session])
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn cleanup_sessions_indexes(
+        &mut self,
+    ) -> Result<SessionIndexCleanupReport, SessionServiceError> {
+        let report = self.repository.cleanup_sessions_indexes().await?;
+        Ok(report)
+    }
+
+    /// returns session ttl limited by idle timeout, absolute session lifetime(anonymous one is shorter)
+    /// and provider tokens expiration, None means the session is expired
+    pub fn get_session_ttl(config: &SessionConfig, session: &Session) -> Option<u64> {
+        let now = Utc::now();
+        let session_lifetime_sec = if session.is_anonymous() {
+            config.anonymous_session_ttl_sec
+        } else {
+            config.session_ttl_sec
+        };
+        let lifetime_left = (session.created_at + Duration::seconds(session_lifetime_sec as i64)
+            - now)
+            .num_seconds();
+        let idle_time_left =
+            (session.updated_at + Duration::seconds(config.session_idle_timeout_sec as i64) - now)
+                .num_seconds();
+        let mut session_ttl = lifetime_left.min(idle_time_left);
+        if let Some(expiration_limit) = session.tokens.get_expiration_limit() {
+            session_ttl = session_ttl.min((expiration_limit - now).num_seconds());
+        }
+        if session_ttl <= 0 {
+            return None;
+        }
+        Some(session_ttl as u64)
+    }
+
+    /// session activity is updated once per interval to not write the session on every request
+    pub fn is_activity_update_required(config: &SessionConfig, session: &Session) -> bool {
+        Utc::now() - session.updated_at
+            >= Duration::seconds(config.session_activity_update_interval_sec as i64)
+    }
+
     pub fn set_cookie_session_id(
         config: &CookieConfiguration,
         response_head: &mut ResponseHead,
@@ -129,6 +264,26 @@ impl SessionService {
         Ok(())
     }
 
+    /// returns the token of `Authorization: Bearer <token>` header
+    pub fn get_bearer_token(request: &ServiceRequest) -> Option<String> {
+        let authorization = request.headers().get(AUTHORIZATION)?.to_str().ok()?;
+        let (scheme, token) = authorization.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("bearer") {
+            log::debug!("Not Bearer authorization scheme on request, ignoring...");
+            return None;
+        }
+        let token = token.trim();
+        if token.is_empty() {
+            return None;
+        }
+        Some(token.to_string())
+    }
+
+    /// opaque session token is alphanumeric, JWT has three dot separated parts
+    pub fn is_jwt(token: &str) -> bool {
+        token.split('.').count() == 3
+    }
+
     pub fn get_cookie_session_id(
         config: &CookieConfiguration,
         request: &ServiceRequest,
@@ -0,0 +1,226 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header, test::TestRequest};
+
+    use bson::oid::ObjectId;
+    use chrono::{DateTime, Duration, Utc};
+
+    use crate::{
+        app::{
+            models::{
+                common::AuthProviders,
+                session::{NewSessionData, Session},
+                session_metadata::SessionMetadata,
+            },
+            services::{
+                cache::{common::CacheServiceType, service::RedisCacheService},
+                session::service::SessionService,
+                traits::session_storage::SessionStorage,
+            },
+        },
+        config::session_config::SessionConfig,
+        tests::test_data::TestData,
+    };
+
+    #[test]
+    fn get_bearer_token() {
+        let get_token = |authorization: Option<&str>| {
+            let mut request = TestRequest::default();
+            if let Some(authorization) = authorization {
+                request = request.insert_header((header::AUTHORIZATION, authorization));
+            }
+            SessionService::get_bearer_token(&request.to_srv_request())
+        };
+
+        assert_eq!(
+            get_token(Some("Bearer session_token")),
+            Some(String::from("session_token"))
+        );
+        // scheme is case insensitive, token is trimmed
+        assert_eq!(
+            get_token(Some("bearer   session_token  ")),
+            Some(String::from("session_token"))
+        );
+        assert_eq!(
+            get_token(Some("Bearer header.payload.signature")),
+            Some(String::from("header.payload.signature"))
+        );
+
+        assert_eq!(get_token(None), None);
+        assert_eq!(get_token(Some("Basic dXNlcjpwYXNzd29yZA==")), None);
+        assert_eq!(get_token(Some("Bearer")), None);
+        assert_eq!(get_token(Some("Bearer    ")), None);
+        assert_eq!(get_token(Some("session_token")), None);
+    }
+
+    #[test]
+    fn is_jwt() {
+        assert_eq!(SessionService::is_jwt("header.payload.signature"), true);
+        assert_eq!(SessionService::is_jwt("session_token"), false);
+        assert_eq!(SessionService::is_jwt("header.payload"), false);
+    }
+
+    #[test]
+    fn get_session_ttl() {
+        let config = get_config();
+        let now = Utc::now();
+
+        // the new session lives till the idle timeout
+        let session = TestData::new().google_session;
+        assert_ttl(SessionService::get_session_ttl(&config, &session), 86400);
+
+        // idle expiry, the activity moves the idle timeout
+        let mut session = TestData::new().google_session;
+        session.updated_at = now - Duration::seconds(3600);
+        assert_ttl(SessionService::get_session_ttl(&config, &session), 82800);
+        session.updated_at = now - Duration::seconds(86401);
+        assert_eq!(SessionService::get_session_ttl(&config, &session), None);
+
+        // absolute lifetime caps the idle timeout of the active session
+        let mut session = TestData::new().google_session;
+        session.created_at = now - Duration::seconds(259200 - 600);
+        assert_ttl(SessionService::get_session_ttl(&config, &session), 600);
+        session.created_at = now - Duration::seconds(259201);
+        assert_eq!(SessionService::get_session_ttl(&config, &session), None);
+
+        // refresh token expiration caps the session
+        let mut session = TestData::new().google_session;
+        set_refresh_token_expire(&mut session, now + Duration::seconds(300));
+        assert_ttl(SessionService::get_session_ttl(&config, &session), 300);
+        set_refresh_token_expire(&mut session, now - Duration::seconds(1));
+        assert_eq!(SessionService::get_session_ttl(&config, &session), None);
+
+        // without refresh token the access token expiration caps the session
+        let mut session = TestData::new().google_session;
+        session.tokens.refresh_token = None;
+        if let Some(access_token) = session.tokens.access_token.as_mut() {
+            access_token.expire = Some(now + Duration::seconds(120));
+        }
+        assert_ttl(SessionService::get_session_ttl(&config, &session), 120);
+
+        // anonymous session has own shorter lifetime
+        let mut session = TestData::new().google_session;
+        session.anonimous = true;
+        assert_ttl(SessionService::get_session_ttl(&config, &session), 3600);
+        session.created_at = now - Duration::seconds(3601);
+        assert_eq!(SessionService::get_session_ttl(&config, &session), None);
+    }
+
+    #[test]
+    fn is_activity_update_required() {
+        let config = get_config();
+        let now = Utc::now();
+
+        let mut session = TestData::new().google_session;
+        assert_eq!(
+            SessionService::is_activity_update_required(&config, &session),
+            false
+        );
+        session.updated_at = now - Duration::seconds(50);
+        assert_eq!(
+            SessionService::is_activity_update_required(&config, &session),
+            false
+        );
+        session.updated_at = now - Duration::seconds(60);
+        assert_eq!(
+            SessionService::is_activity_update_required(&config, &session),
+            true
+        );
+    }
+
+    #[actix_rt::test]
+    async fn remove_rotated_sessions() {
+        let redis_cache = RedisCacheService::new(CacheServiceType::Session)
+            .expect("Error to create Session Cache");
+        let mut session_service = SessionService::new(SessionConfig::new(), redis_cache.clone());
+        let user_id = ObjectId::new();
+        let user_sessions_key = Session::get_user_sessions_key(&user_id.to_string());
+
+        // anonymous session the login is started with
+        let anonymous_session = Session::get_anonymous_session(SessionMetadata::new());
+        assert_eq!(redis_cache.set(&anonymous_session, 60).is_ok(), true);
+        // the user session the client had before the login
+        let previous_session = set_user_session(&mut session_service, user_id).await;
+        let new_session = set_user_session(&mut session_service, user_id).await;
+
+        // the new session can be in the previous ones, it's kept
+        let result = session_service
+            .remove_rotated_sessions(
+                &new_session,
+                vec![
+                    anonymous_session.clone(),
+                    previous_session.clone(),
+                    new_session.clone(),
+                ],
+            )
+            .await;
+        assert_eq!(result.is_ok(), true);
+
+        for (session, is_kept) in [
+            (&anonymous_session, false),
+            (&previous_session, false),
+            (&new_session, true),
+        ] {
+            match redis_cache.get_value::<Session>(&Session::get_session_key(&session.id)) {
+                Ok(stored_session) => assert_eq!(stored_session.is_some(), is_kept),
+                Err(err) => assert!(false, "Error to get session: {}", err),
+            };
+        }
+        match redis_cache.get_all_set_values(&user_sessions_key) {
+            Ok(index) => {
+                assert_eq!(index.len(), 1);
+                assert_eq!(
+                    index.contains_key(&Session::get_session_key(&new_session.id)),
+                    true
+                );
+            }
+            Err(err) => assert!(false, "Error to get user sessions index: {}", err),
+        };
+
+        let result = session_service.remove_all_user_sessions(user_id).await;
+        assert_eq!(result.is_ok(), true);
+    }
+
+    async fn set_user_session(session_service: &mut SessionService, user_id: ObjectId) -> Session {
+        match session_service
+            .set_new_session(
+                NewSessionData {
+                    anonimous: false,
+                    auth_provider: AuthProviders::Google,
+                    user_id,
+                    tokens: TestData::new().google_session.tokens,
+                    session_metadata: SessionMetadata::new(),
+                },
+                None,
+                &[],
+            )
+            .await
+        {
+            Ok((session, _)) => session,
+            Err(err) => panic!("Error to set user session: {}", err),
+        }
+    }
+
+    fn get_config() -> SessionConfig {
+        let mut session_config = SessionConfig::new();
+        session_config.session_ttl_sec = 259200;
+        session_config.session_idle_timeout_sec = 86400;
+        session_config.anonymous_session_ttl_sec = 3600;
+        session_config.session_activity_update_interval_sec = 60;
+        session_config
+    }
+
+    fn set_refresh_token_expire(session: &mut Session, expire: DateTime<Utc>) {
+        if let Some(refresh_token) = session.tokens.refresh_token.as_mut() {
+            refresh_token.expire = Some(expire);
+        }
+    }
+
+    // the ttl is counted from the current time, so it can be a second less
+    fn assert_ttl(ttl: Option<u64>, expected_ttl: u64) {
+        match ttl {
+            Some(ttl) => assert_eq!(ttl <= expected_ttl && ttl + 1 >= expected_ttl, true),
+            None => assert!(false, "Session is expired!?"),
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use mongodb::options::ClientOptions;
-use mongodb::{Client, Collection, Database};
+use mongodb::bson::{doc, Document};
+use mongodb::options::{ClientOptions, IndexOptions};
+use mongodb::{Client, Collection, Database, IndexModel};
 
 use crate::config::mongodb_config::MongoDBConfig;
 
@@ -7,7 +8,18 @@ use super::error::StorageServiceError;
 
 pub trait CollectionType {}
 
-#[derive(Debug)]
+// user fields which have to be uniq across all users, the fields are optional
+// so uniqueness is checked for string values only
+const USER_UNIQUE_FIELDS: [&str; 6] = [
+    "cyber_sherlock.email",
+    "cyber_sherlock.phone",
+    "cyber_sherlock.normalized_email",
+    "cyber_sherlock.normalized_phone",
+    "google.user_id",
+    "facebook.user_id",
+];
+
+#[derive(Debug, Clone)]
 pub struct StorageService {
     pub config: MongoDBConfig,
     database: Database,
@@ -25,7 +37,43 @@ impl StorageService {
         let client = Client::with_options(client_options)?;
         let database = client.database(&config.database);
         log::debug!("\nMongoDB database {} is connected.\n", &config.database);
-        Ok(StorageService { config, database })
+        let storage_service = StorageService { config, database };
+        storage_service.create_user_indexes().await?;
+        Ok(storage_service)
+    }
+
+    // creating of existing index is no-op, so it's safe to call it on every startup
+    async fn create_user_indexes(&self) -> Result<(), StorageServiceError> {
+        let indexes: Vec<IndexModel> = USER_UNIQUE_FIELDS
+            .iter()
+            .map(|field| {
+                IndexModel::builder()
+                    .keys(doc! { field.to_string(): 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .unique(true)
+                            .partial_filter_expression(
+                                doc! { field.to_string(): { "$type": "string" } },
+                            )
+                            .build(),
+                    )
+                    .build()
+            })
+            .collect();
+        if let Err(err) = self
+            .database
+            .collection::<Document>(&self.config.user_collection)
+            .create_indexes(indexes, None)
+            .await
+        {
+            log::error!(
+                "Unable to create uniq indexes on {} collection, check duplicates: {}",
+                &self.config.user_collection,
+                err
+            );
+            return Err(err.into());
+        }
+        Ok(())
     }
 
     pub fn get_collection<T: CollectionType>(&self, collection_name: &str) -> Collection<T> {
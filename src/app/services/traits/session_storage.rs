@@ -2,5 +2,12 @@ use crate::app::{models::session::Session, services::cache::error::CacheServiceE
 
 pub trait SessionStorage {
     fn load(&self, key: &str) -> Result<Option<Session>, CacheServiceError>;
+    /// loads stored sessions of the user sessions index
+    fn load_user_sessions(&self, user_id: &str) -> Result<Vec<Session>, CacheServiceError>;
     fn set(&self, session: &Session, ttl: u64) -> Result<(), CacheServiceError>;
+    fn remove(&self, session: &Session) -> Result<(), CacheServiceError>;
+    /// counts anonymous sessions stored from the client ip during the ttl
+    fn count_anonymous_session(&self, client_ip: &str, ttl: u64) -> Result<u64, CacheServiceError>;
+    /// returns number of anonymous sessions stored from the client ip during the ttl
+    fn get_anonymous_sessions_count(&self, client_ip: &str) -> Result<u64, CacheServiceError>;
 }
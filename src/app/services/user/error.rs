@@ -1,10 +1,12 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
 use thiserror::Error;
 
 use crate::app::{
     repositories::user::error::UserRepositoryError,
     services::{
-        cache::error::CacheServiceError, session::error::SessionServiceError,
-        storage::error::StorageServiceError,
+        cache::error::CacheServiceError, common::error_with_code_as_json,
+        session::error::SessionServiceError, storage::error::StorageServiceError,
     },
 };
 
@@ -21,11 +23,75 @@ pub enum UserServiceError {
 
     #[error("StorageService error")]
     StorageServiceError,
+
+    #[error("Unable to remove the last user login method")]
+    LastLoginMethodError,
+
+    #[error("User with the credentials exists")]
+    DuplicateUserError,
+
+    #[error("User not found")]
+    UserNotFoundError,
+
+    #[error("Unable to merge user into itself")]
+    BadMergeRequestError,
+
+    #[error("Max number of active sessions is reached")]
+    SessionLimitExceededError,
+
+    #[error("{0}")]
+    UserStatusError(UserStatusError),
+
+    // keeps merge token to confirm the merge
+    #[error("Account merge confirmation required")]
+    MergeConfirmationRequired(String),
+}
+
+/// user is not active, clients get the error code to show the user the reason
+#[derive(Debug, Error)]
+pub enum UserStatusError {
+    #[error("User account is suspended")]
+    Suspended {
+        reason: String,
+        until: Option<DateTime<Utc>>,
+    },
+
+    #[error("User account is pending deletion")]
+    PendingDeletion,
+}
+
+impl UserStatusError {
+    pub fn get_code(&self) -> &'static str {
+        match self {
+            UserStatusError::Suspended { .. } => "account_suspended",
+            UserStatusError::PendingDeletion => "account_pending_deletion",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut json_error = error_with_code_as_json(&self.to_string(), self.get_code());
+        if let UserStatusError::Suspended { reason, until } = self {
+            json_error["reason"] = json!(reason);
+            json_error["until"] =
+                json!(until.map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)));
+        }
+        json_error
+    }
+}
+
+impl From<UserStatusError> for UserServiceError {
+    fn from(err: UserStatusError) -> Self {
+        log::debug!("UserStatusError: {:?}", err);
+        return UserServiceError::UserStatusError(err);
+    }
 }
 
 impl From<UserRepositoryError> for UserServiceError {
     fn from(err: UserRepositoryError) -> Self {
         log::debug!("UserRepositoryError: {:?}", err);
+        if let UserRepositoryError::Duplicate = err {
+            return UserServiceError::DuplicateUserError;
+        }
         return UserServiceError::UserRepositoryError;
     }
 }
@@ -33,6 +99,9 @@ impl From<UserRepositoryError> for UserServiceError {
 impl From<SessionServiceError> for UserServiceError {
     fn from(err: SessionServiceError) -> Self {
         log::debug!("SessionServiceError: {:?}", err);
+        if let SessionServiceError::SessionLimitExceededError = err {
+            return UserServiceError::SessionLimitExceededError;
+        }
         return UserServiceError::SessionServiceError;
     }
 }
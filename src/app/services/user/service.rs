@@ -1,31 +1,55 @@
 use actix_web::dev::ResponseHead;
 use bson::oid::ObjectId;
+use chrono::{Duration, Utc};
+use serde_json::Value;
 
 use crate::{
     app::{
         models::{
-            session::{NewSessionData, Session},
+            common::AuthProviders,
+            login_record::LoginRecord,
+            pending_merge::PendingMerge,
+            role::Role,
+            session::{NewSessionData, Session, SessionDelivery},
             session_metadata::SessionMetadata,
             session_tokens::SessionTokens,
             user::User,
-            user_profile::UserProfile,
+            user_audit::{UserAuditEvent, UserAuditRecord},
+            user_data_export::UserDataExport,
+            user_event::{UserEvent, UserEventType},
+            user_merge_report::UserMergeReport,
+            user_profile::{CyberSherlockProfileUpdate, UserProfile},
+            user_status::{Suspension, UserStatus},
         },
-        providers::cyber_sherlock::common::Credentials,
-        repositories::user::repository::UserRepository,
+        providers::{
+            cyber_sherlock::common::{ContactChange, Credentials},
+            notification::provider::NotificationProvider,
+        },
+        repositories::user::repository::{NormalizationReport, UserRepository, UserSearch},
         services::{
-            cache::service::RedisCacheService, session::service::SessionService,
+            cache::service::RedisCacheService,
+            common::{result_as_json, session_token_as_json},
+            session::service::SessionService,
             storage::service::StorageService,
         },
     },
     config::{session_config::SessionConfig, user_config::UserConfig},
 };
 
-use super::error::UserServiceError;
+use super::error::{UserServiceError, UserStatusError};
+
+#[derive(Debug, Default)]
+pub struct PurgeReport {
+    pub purged: usize,
+    pub failed: usize,
+}
 
 #[derive(Debug)]
 pub struct UserService {
+    config: UserConfig,
     session_service: SessionService,
     user_repository: UserRepository,
+    notification_provider: NotificationProvider,
 }
 
 impl UserService {
@@ -34,13 +58,17 @@ impl UserService {
         storage_service: StorageService,
         user_cache_service: RedisCacheService,
         session_cache_service: RedisCacheService,
+        notification_provider: NotificationProvider,
     ) -> Result<Self, UserServiceError> {
-        let user_repository = UserRepository::new(user_cache_service, config, storage_service);
+        let user_repository =
+            UserRepository::new(user_cache_service, config.clone(), storage_service);
         let session_config = SessionConfig::new();
         let session_service = SessionService::new(session_config, session_cache_service);
         Ok(UserService {
+            config,
             session_service,
             user_repository,
+            notification_provider,
         })
     }
 
@@ -64,17 +92,226 @@ impl UserService {
         &mut self,
         user_profile: UserProfile,
     ) -> Result<User, UserServiceError> {
+        let user_profile = self.normalize_profile(user_profile);
         if let Some(user) = self.get_user_by_profile(user_profile.clone()).await? {
+            // pending deletion user is restored on the login by set_new_session,
+            // which rejects the suspended user first
             Ok(self
                 .update_user_with_profile(&user.id, user_profile)
                 .await?)
         } else {
+            if self.config.auto_merge_verified_email {
+                if let Some(user) = self.merge_user_profile_by_email(&user_profile).await? {
+                    return Ok(user);
+                }
+            }
             let new_user = User::new(user_profile);
             self.user_repository.insert_user(&new_user).await?;
             Ok(new_user)
         }
     }
 
+    async fn restore_user(
+        &mut self,
+        user: &User,
+        auth_provider: AuthProviders,
+    ) -> Result<User, UserServiceError> {
+        let restored_user = self
+            .user_repository
+            .restore_user(
+                &user.id,
+                UserAuditRecord::new(UserAuditEvent::AccountRestored, auth_provider, None),
+            )
+            .await?;
+        log::info!("User {} restored", &user.id);
+        Ok(restored_user)
+    }
+
+    /// attaches the profile to existing user with the same email verified on both sides,
+    /// unverified or conflicting matches have to be confirmed by the existing user
+    async fn merge_user_profile_by_email(
+        &mut self,
+        user_profile: &UserProfile,
+    ) -> Result<Option<User>, UserServiceError> {
+        let email = match UserProfile::get_email(user_profile) {
+            Some(email) => email,
+            None => return Ok(None),
+        };
+        let existen_user = match self.user_repository.find_user_by_email(&email).await? {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+        let provider = UserProfile::get_provider(user_profile);
+        // the user has other account of the provider linked, the merge can't be confirmed ever,
+        // so the profile gets a new user
+        if existen_user.has_profile(&provider) {
+            log::info!(
+                "User {} with email {} has other {} profile, no merge",
+                &existen_user.id,
+                email,
+                provider.to_string()
+            );
+            return Ok(None);
+        }
+        // the existing user could be registered with not own unverified email,
+        // auto merge would attach the email owner profile to that user
+        if UserProfile::get_verified_email(user_profile).is_some()
+            && existen_user.has_verified_email(&email)
+        {
+            let user = self
+                .link_user_profile(
+                    &existen_user,
+                    user_profile.clone(),
+                    UserAuditRecord::new(
+                        UserAuditEvent::ProfileMerged,
+                        provider,
+                        Some(format!("verified email {}", email)),
+                    ),
+                )
+                .await?;
+            log::info!(
+                "{} profile merged to User {} by verified email",
+                provider.to_string(),
+                &user.id
+            );
+            return Ok(Some(user));
+        }
+        let merge_token = PendingMerge::generate_merge_token();
+        self.user_repository.set_pending_merge(
+            &merge_token,
+            &PendingMerge {
+                user_id: existen_user.id,
+                profile: user_profile.clone(),
+            },
+        )?;
+        Err(UserServiceError::MergeConfirmationRequired(merge_token))
+    }
+
+    pub async fn link_user_profile(
+        &mut self,
+        user: &User,
+        user_profile: UserProfile,
+        audit_record: UserAuditRecord,
+    ) -> Result<User, UserServiceError> {
+        let updated_user = self
+            .user_repository
+            .link_user_profile(
+                &user.id,
+                attach_profile_to_user(self.normalize_profile(user_profile), &user.id),
+                audit_record,
+            )
+            .await?;
+        Ok(updated_user)
+    }
+
+    /// merges source user into target user: copies source user profiles(target user keeps
+    /// its own profiles on conflict), moves source user sessions and deletes source user
+    pub async fn merge_users(
+        &mut self,
+        target_user_id: &ObjectId,
+        source_user_id: &ObjectId,
+        dry_run: bool,
+    ) -> Result<UserMergeReport, UserServiceError> {
+        if target_user_id == source_user_id {
+            return Err(UserServiceError::BadMergeRequestError);
+        }
+        let target_user = self
+            .get_user_by_id(target_user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFoundError)?;
+        let source_user = self
+            .get_user_by_id(source_user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFoundError)?;
+
+        let mut profiles_to_copy: Vec<UserProfile> = vec![];
+        let mut copied_profiles: Vec<AuthProviders> = vec![];
+        let mut conflicting_profiles: Vec<AuthProviders> = vec![];
+        for provider in source_user.get_linked_providers() {
+            if target_user.has_profile(&provider) {
+                conflicting_profiles.push(provider);
+            } else if let Some(profile) = source_user.get_profile(&provider) {
+                profiles_to_copy.push(attach_profile_to_user(profile, &target_user.id));
+                copied_profiles.push(provider);
+            }
+        }
+
+        // sessions of conflicting profiles belong to provider account which is not kept
+        let (sessions_to_remove, sessions_to_move): (Vec<Session>, Vec<Session>) = self
+            .session_service
+            .get_all_sessions(source_user.id)
+            .await?
+            .into_iter()
+            .partition(|s| {
+                conflicting_profiles
+                    .iter()
+                    .any(|provider| provider.is_equal(&s.auth_provider))
+            });
+
+        let mut report = UserMergeReport {
+            target_user_id: target_user.id,
+            source_user_id: source_user.id,
+            dry_run,
+            copied_profiles,
+            conflicting_profiles,
+            moved_sessions: sessions_to_move.len(),
+            removed_sessions: sessions_to_remove.len(),
+        };
+        if dry_run {
+            return Ok(report);
+        }
+
+        // source user has to be deleted before to keep provider profiles uniq
+        self.user_repository.delete_by_id(&source_user.id).await?;
+        let audit_record = UserAuditRecord::new(
+            UserAuditEvent::AccountsMerged,
+            source_user.active_profile,
+            Some(format!("merged user {}", source_user.id)),
+        );
+        if let Err(err) = self
+            .user_repository
+            .link_user_profiles(&target_user.id, profiles_to_copy, audit_record)
+            .await
+        {
+            log::error!(
+                "Error to merge User {} into User {}: {}. Restoring source user...",
+                &source_user.id,
+                &target_user.id,
+                err
+            );
+            self.user_repository.insert_user(&source_user).await?;
+            return Err(err.into());
+        }
+        self.session_service
+            .remove_user_sessions(source_user.id, sessions_to_remove)
+            .await?;
+        report.moved_sessions = self
+            .session_service
+            .move_sessions(source_user.id, target_user.id, sessions_to_move)
+            .await?;
+        self.user_repository
+            .invalidate_user_cache(&target_user.id)?;
+        log::info!(
+            "User {} merged into User {}: {:?}",
+            &source_user.id,
+            &target_user.id,
+            &report
+        );
+        Ok(report)
+    }
+
+    /// returns the pending merge of the user, merge token of other user is not found
+    /// and stays for its owner
+    pub fn take_pending_merge(
+        &self,
+        user_id: &ObjectId,
+        merge_token: &str,
+    ) -> Result<Option<PendingMerge>, UserServiceError> {
+        Ok(self
+            .user_repository
+            .take_pending_merge(user_id, merge_token)?)
+    }
+
     pub async fn update_user_with_profile(
         &mut self,
         user_id: &ObjectId,
@@ -82,7 +319,40 @@ impl UserService {
     ) -> Result<User, UserServiceError> {
         let user = self
             .user_repository
-            .update_user_with_profile(user_id, user_profile)
+            .update_user_with_profile(user_id, self.normalize_profile(user_profile))
+            .await?;
+        Ok(user)
+    }
+
+    fn normalize_profile(&self, user_profile: UserProfile) -> UserProfile {
+        UserProfile::normalize(user_profile, &self.config.phone_default_region)
+    }
+
+    /// sets normalized email and phone for all stored users
+    pub async fn normalize_users(&mut self) -> Result<NormalizationReport, UserServiceError> {
+        Ok(self.user_repository.normalize_all_users().await?)
+    }
+
+    pub async fn update_cyber_sherlock_profile(
+        &mut self,
+        user_id: &ObjectId,
+        profile_update: CyberSherlockProfileUpdate,
+    ) -> Result<User, UserServiceError> {
+        let user = self
+            .user_repository
+            .update_cyber_sherlock_profile(user_id, profile_update)
+            .await?;
+        Ok(user)
+    }
+
+    pub async fn update_cyber_sherlock_contact(
+        &mut self,
+        user_id: &ObjectId,
+        change: ContactChange,
+    ) -> Result<User, UserServiceError> {
+        let user = self
+            .user_repository
+            .update_cyber_sherlock_contact(user_id, change)
             .await?;
         Ok(user)
     }
@@ -106,17 +376,113 @@ impl UserService {
         Ok(user)
     }
 
+    /// returns the copy of the user repository to read users without holding the UserService lock,
+    /// the copy shares the storage and cache connections
+    pub fn get_user_repository(&self) -> UserRepository {
+        self.user_repository.clone()
+    }
+
+    /// returns the error in case the user is not active
+    pub fn check_user_status(user: &User) -> Result<(), UserStatusError> {
+        match user.get_status() {
+            UserStatus::Active => Ok(()),
+            UserStatus::Suspended => Err(UserService::get_suspended_error(user)),
+            UserStatus::PendingDeletion => Err(UserStatusError::PendingDeletion),
+        }
+    }
+
+    fn get_suspended_error(user: &User) -> UserStatusError {
+        UserStatusError::Suspended {
+            reason: user
+                .suspension
+                .as_ref()
+                .map(|suspension| suspension.reason.clone())
+                .unwrap_or_default(),
+            until: user
+                .suspension
+                .as_ref()
+                .and_then(|suspension| suspension.until),
+        }
+    }
+
+    /// sets a new user session in the user active sessions limit, only active user can login,
+    /// the user is notified about sessions evicted by the limit
     pub async fn set_new_session(
         &mut self,
+        user: &User,
         new_session_data: NewSessionData,
     ) -> Result<Session, UserServiceError> {
-        let session = self
+        match UserService::check_user_status(user) {
+            Ok(_) => {}
+            // login during deletion grace period cancels the deletion
+            Err(UserStatusError::PendingDeletion) => {
+                // suspended user can't cancel the deletion by login
+                if user.is_suspended() {
+                    log::debug!("User {} is suspended, login is rejected", &user.id);
+                    return Err(UserService::get_suspended_error(user).into());
+                }
+                self.restore_user(user, new_session_data.auth_provider)
+                    .await?;
+            }
+            Err(err) => {
+                log::debug!("User {} is not active, login is rejected", &user.id);
+                return Err(err.into());
+            }
+        }
+        let (session, evicted_sessions) = self
             .session_service
-            .set_new_session(new_session_data)
+            .set_new_session(new_session_data, user.max_sessions, &user.get_role_names())
             .await?;
+        if !evicted_sessions.is_empty() {
+            self.notify_evicted_sessions(user, &evicted_sessions);
+        }
+        self.add_login_record(user, &session).await;
         Ok(session)
     }
 
+    async fn add_login_record(&mut self, user: &User, session: &Session) {
+        // the session is created already, login history fail should not fail the login
+        if let Err(err) = self
+            .user_repository
+            .add_login_record(&user.id, &LoginRecord::new(session))
+            .await
+        {
+            log::error!("Unable to add login record of User {}: {}", &user.id, err);
+        }
+    }
+
+    fn notify_evicted_sessions(&mut self, user: &User, evicted_sessions: &[Session]) {
+        log::info!(
+            "{} sessions of User {} evicted by the session limit",
+            evicted_sessions.len(),
+            &user.id
+        );
+        let email = match user.get_verified_email() {
+            Some(email) => email,
+            None => {
+                log::debug!("User {} has no verified email to notify", &user.id);
+                return;
+            }
+        };
+        for session in evicted_sessions {
+            let message = format!(
+                "You were signed out on {} because of the new login, max number of active sessions is reached",
+                session.metadata.user_agent
+            );
+            // the session is evicted already, notification fail should not fail the login
+            if let Err(err) = self
+                .notification_provider
+                .send_email(&message, email.clone())
+            {
+                log::error!(
+                    "Unable to notify User {} about evicted session: {}",
+                    &user.id,
+                    err
+                );
+            }
+        }
+    }
+
     // TODO: investigate if this method should split logic per each provider
     pub async fn get_user_session(
         &mut self,
@@ -154,13 +520,16 @@ impl UserService {
                 user_session.tokens.update_tokens(tokens.clone());
                 // TODO: clone user session with the same token
                 let new_user_session = self
-                    .set_new_session(NewSessionData {
-                        anonimous: false,
-                        auth_provider: provider,
-                        user_id: exiten_user.id,
-                        tokens: user_session.tokens,
-                        session_metadata,
-                    })
+                    .set_new_session(
+                        &exiten_user,
+                        NewSessionData {
+                            anonimous: false,
+                            auth_provider: provider,
+                            user_id: exiten_user.id,
+                            tokens: user_session.tokens,
+                            session_metadata,
+                        },
+                    )
                     .await?;
                 Ok(Some(new_user_session))
             } else {
@@ -172,13 +541,16 @@ impl UserService {
             // - set in session user client
             let user = self.create_user_with_profile(user_profile).await?;
             let session = self
-                .set_new_session(NewSessionData {
-                    anonimous: false,
-                    auth_provider: provider,
-                    user_id: user.id,
-                    tokens: tokens,
-                    session_metadata,
-                })
+                .set_new_session(
+                    &user,
+                    NewSessionData {
+                        anonimous: false,
+                        auth_provider: provider,
+                        user_id: user.id,
+                        tokens: tokens,
+                        session_metadata,
+                    },
+                )
                 .await?;
             Ok(Some(session))
         }
@@ -196,23 +568,414 @@ impl UserService {
         )?)
     }
 
+    /// login response body, the session token is in the body only for the token delivery,
+    /// provider tokens are never exposed, CyberSherlock access JWT is accepted as Bearer token
+    pub fn get_login_result_json(
+        &self,
+        result: &str,
+        session_delivery: SessionDelivery,
+        session: &Session,
+    ) -> Value {
+        match session_delivery {
+            SessionDelivery::Cookie => result_as_json(result),
+            SessionDelivery::Token => {
+                let expires_in =
+                    SessionService::get_session_ttl(&self.session_service.config, session)
+                        .unwrap_or(0);
+                let access_token = if session
+                    .auth_provider
+                    .is_equal(&AuthProviders::CyberSherlock)
+                {
+                    session
+                        .tokens
+                        .access_token
+                        .as_ref()
+                        .map(|token| token.token_string.as_str())
+                } else {
+                    None
+                };
+                session_token_as_json(result, &session.id, expires_in, access_token)
+            }
+        }
+    }
+
+    /// every privilege change(login, MFA step-up, password change) has to issue a new session
+    /// (its metadata is taken from the previous session) to prevent session fixation:
+    /// the new session cookie is set(the token delivered session is in the response body)
+    /// and the previous sessions of the client are removed.
+    /// There is no anonymous session data to migrate: anonymous session has empty tokens
+    /// and its metadata is passed to the new session on creation,
+    /// login flow data(state, PKCE verifier) is kept in login cache data by the state, not by the session
+    pub async fn rotate_session(
+        &mut self,
+        response_head: &mut ResponseHead,
+        session_delivery: SessionDelivery,
+        new_session: &Session,
+        previous_sessions: Vec<Session>,
+    ) -> Result<(), UserServiceError> {
+        if session_delivery == SessionDelivery::Cookie {
+            self.set_session_cookie(response_head, new_session)?;
+        }
+        self.session_service
+            .remove_rotated_sessions(new_session, previous_sessions)
+            .await?;
+        Ok(())
+    }
+
+    /// removes the session only, other user devices keep their sessions
     pub async fn logout_by_session(&mut self, session: Session) -> Result<(), UserServiceError> {
         self.session_service
-            .remove_sessions_by_session(session)
+            .remove_user_sessions(session.user_id, vec![session])
             .await?;
         Ok(())
     }
 
-    pub async fn remove_anonymous_sessions(
+    /// removes user session by its public id, returns false in case no session found
+    pub async fn revoke_session(
         &mut self,
-        sessions: Vec<Session>,
-    ) -> Result<(), UserServiceError> {
+        user_id: &ObjectId,
+        public_session_id: &str,
+    ) -> Result<bool, UserServiceError> {
+        let session = self
+            .get_all_user_sessions(user_id)
+            .await?
+            .into_iter()
+            .find(|s| s.get_public_id() == public_session_id);
+        match session {
+            Some(session) => {
+                self.session_service
+                    .remove_user_sessions(user_id.to_owned(), vec![session])
+                    .await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// removes all user sessions except the current one, returns number of removed sessions
+    pub async fn revoke_other_sessions(
+        &mut self,
+        current_session: &Session,
+    ) -> Result<usize, UserServiceError> {
+        let removed_sessions = self
+            .session_service
+            .remove_other_user_sessions(current_session.user_id, &current_session.id)
+            .await?;
+        Ok(removed_sessions)
+    }
+
+    /// removes all user sessions of all providers
+    pub async fn logout_everywhere(&mut self, user_id: &ObjectId) -> Result<(), UserServiceError> {
+        self.session_service
+            .remove_all_user_sessions(user_id.to_owned())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_all_user_sessions(
+        &mut self,
+        user_id: &ObjectId,
+    ) -> Result<Vec<Session>, UserServiceError> {
+        let sessions = self
+            .session_service
+            .get_all_sessions(user_id.to_owned())
+            .await?;
+        Ok(sessions)
+    }
+
+    pub async fn get_user_sessions_by_provider(
+        &mut self,
+        user_id: &ObjectId,
+        auth_provider: AuthProviders,
+    ) -> Result<Vec<Session>, UserServiceError> {
+        let sessions = self
+            .session_service
+            .get_sessions(user_id.to_owned(), auth_provider)
+            .await?;
+        Ok(sessions)
+    }
+
+    /// replaces the user roles, assigned_by is None for the roles set by the admin command
+    pub async fn set_user_roles(
+        &mut self,
+        user_id: &ObjectId,
+        mut roles: Vec<Role>,
+        assigned_by: Option<&ObjectId>,
+    ) -> Result<User, UserServiceError> {
+        let user = self
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFoundError)?;
+        roles.sort_by_key(|role| role.to_string());
+        roles.dedup();
+        let role_names: Vec<String> = roles.iter().map(|role| role.to_string()).collect();
+        let details = match assigned_by {
+            Some(admin_id) => format!("roles [{}] set by {}", role_names.join(", "), admin_id),
+            None => format!("roles [{}] set by admin command", role_names.join(", ")),
+        };
+        let audit_record = UserAuditRecord::new(
+            UserAuditEvent::RolesChanged,
+            user.active_profile,
+            Some(details),
+        );
+        let updated_user = self
+            .user_repository
+            .update_user_roles(&user.id, &roles, audit_record)
+            .await?;
+        Ok(updated_user)
+    }
+
+    /// sets the user own max sessions limit, it takes effect on the next login of the user
+    pub async fn set_user_max_sessions(
+        &mut self,
+        user_id: &ObjectId,
+        max_sessions: Option<u64>,
+        changed_by: &ObjectId,
+    ) -> Result<User, UserServiceError> {
+        let user = self
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFoundError)?;
+        let details = match max_sessions {
+            Some(max_sessions) => format!("max sessions {} set by {}", max_sessions, changed_by),
+            None => format!("max sessions removed by {}", changed_by),
+        };
+        let audit_record = UserAuditRecord::new(
+            UserAuditEvent::MaxSessionsChanged,
+            user.active_profile,
+            Some(details),
+        );
+        let updated_user = self
+            .user_repository
+            .update_user_max_sessions(&user.id, max_sessions, audit_record)
+            .await?;
+        Ok(updated_user)
+    }
+
+    /// returns the page of found users and total number of found users, page starts from 1
+    pub async fn search_users(
+        &self,
+        search: &UserSearch,
+        page: u64,
+        per_page: u64,
+    ) -> Result<(Vec<User>, u64), UserServiceError> {
+        let skip = page.saturating_sub(1).saturating_mul(per_page);
+        let (users, total) = self
+            .user_repository
+            .search_users(search, skip, per_page as i64)
+            .await?;
+        Ok((users, total))
+    }
+
+    /// suspends the user and removes all the user sessions
+    pub async fn suspend_user(
+        &mut self,
+        user_id: &ObjectId,
+        suspension: Suspension,
+    ) -> Result<User, UserServiceError> {
+        let user = self
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFoundError)?;
+        let details = format!(
+            "suspended by {}: {}",
+            suspension.suspended_by, suspension.reason
+        );
+        let audit_record = UserAuditRecord::new(
+            UserAuditEvent::AccountSuspended,
+            user.active_profile,
+            Some(details),
+        );
+        let updated_user = self
+            .user_repository
+            .suspend_user(&user.id, &suspension, audit_record)
+            .await?;
+        self.session_service
+            .remove_all_user_sessions(user.id)
+            .await?;
+        Ok(updated_user)
+    }
+
+    /// lifts the user suspension, the user has to login again
+    pub async fn reactivate_user(
+        &mut self,
+        user_id: &ObjectId,
+        reactivated_by: &ObjectId,
+    ) -> Result<User, UserServiceError> {
+        let user = self
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFoundError)?;
+        let audit_record = UserAuditRecord::new(
+            UserAuditEvent::AccountReactivated,
+            user.active_profile,
+            Some(format!("reactivated by {}", reactivated_by)),
+        );
+        let updated_user = self
+            .user_repository
+            .reactivate_user(&user.id, audit_record)
+            .await?;
+        Ok(updated_user)
+    }
+
+    /// sets the new CyberSherlock password hash and removes all user sessions
+    pub async fn reset_password(
+        &mut self,
+        user_id: &ObjectId,
+        hash: &str,
+    ) -> Result<User, UserServiceError> {
+        let audit_record = UserAuditRecord::new(
+            UserAuditEvent::PasswordReset,
+            AuthProviders::CyberSherlock,
+            None,
+        );
+        let user = self
+            .user_repository
+            .update_cyber_sherlock_hash(user_id, hash, audit_record)
+            .await?;
         self.session_service
-            .remove_sessions(sessions.into_iter().filter(|s| s.is_anonymous()).collect())
+            .remove_all_user_sessions(user.id)
             .await?;
+        Ok(user)
+    }
+
+    pub async fn set_active_profile(
+        &mut self,
+        user_id: &ObjectId,
+        provider: AuthProviders,
+    ) -> Result<User, UserServiceError> {
+        let user = self
+            .user_repository
+            .update_active_profile(user_id, &provider)
+            .await?;
+        Ok(user)
+    }
+
+    /// removes provider profile from the user and all user sessions of the provider,
+    /// the caller has to check the user has another login method before
+    pub async fn unlink_provider(
+        &mut self,
+        user: &User,
+        provider: AuthProviders,
+    ) -> Result<User, UserServiceError> {
+        // active profile can't point to removed profile
+        let active_profile = if user.active_profile.is_equal(&provider) {
+            match user
+                .get_linked_providers()
+                .into_iter()
+                .find(|p| !p.is_equal(&provider))
+            {
+                Some(p) => p,
+                None => return Err(UserServiceError::LastLoginMethodError),
+            }
+        } else {
+            user.active_profile
+        };
+        let updated_user = self
+            .user_repository
+            .remove_user_profile(&user.id, &provider, &active_profile)
+            .await?;
+        self.session_service
+            .remove_sessions_by_provider(user.id, provider)
+            .await?;
+        Ok(updated_user)
+    }
+
+    pub async fn export_user_data(
+        &mut self,
+        user: &User,
+    ) -> Result<UserDataExport, UserServiceError> {
+        let sessions = self.session_service.get_all_sessions(user.id).await?;
+        Ok(UserDataExport::new(user, sessions))
+    }
+
+    /// keeps the export for emailed link, returns the link
+    pub fn set_user_data_export(
+        &self,
+        user_data_export: &UserDataExport,
+    ) -> Result<String, UserServiceError> {
+        let export_token = UserDataExport::generate_export_token();
+        self.user_repository
+            .set_user_data_export(&export_token, user_data_export)?;
+        Ok(format!(
+            "{}/{}",
+            self.config.data_export_url.trim_end_matches('/'),
+            export_token
+        ))
+    }
+
+    pub fn take_user_data_export(
+        &self,
+        export_token: &str,
+    ) -> Result<Option<UserDataExport>, UserServiceError> {
+        Ok(self.user_repository.take_user_data_export(export_token)?)
+    }
+
+    /// removes all user sessions and deletes the user, in case of deletion grace period
+    /// the user is soft deleted and returned, it's purged by purge_deleted_users later
+    pub async fn delete_user(
+        &mut self,
+        user: &User,
+        auth_provider: AuthProviders,
+    ) -> Result<Option<User>, UserServiceError> {
+        self.session_service
+            .remove_all_user_sessions(user.id)
+            .await?;
+        if self.config.deletion_grace_period_sec <= 0 {
+            self.purge_user(&user.id).await?;
+            return Ok(None);
+        }
+        let purge_at = Utc::now() + Duration::seconds(self.config.deletion_grace_period_sec);
+        let deleted_user = self
+            .user_repository
+            .schedule_user_deletion(
+                &user.id,
+                purge_at,
+                UserAuditRecord::new(
+                    UserAuditEvent::AccountDeletionScheduled,
+                    auth_provider,
+                    None,
+                ),
+            )
+            .await?;
+        self.publish_user_event(UserEvent::new(
+            UserEventType::UserDeletionScheduled,
+            user.id,
+        ));
+        Ok(Some(deleted_user))
+    }
+
+    /// deletes soft deleted users with expired grace period
+    pub async fn purge_deleted_users(&mut self) -> Result<PurgeReport, UserServiceError> {
+        let users = self.user_repository.find_users_to_purge().await?;
+        let mut report = PurgeReport::default();
+        for user in users {
+            // one failing user must not keep the others past the grace period
+            match self.purge_user(&user.id).await {
+                Ok(_) => report.purged += 1,
+                Err(err) => {
+                    log::error!("Unable to purge User {}: {}", &user.id, err);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn purge_user(&mut self, user_id: &ObjectId) -> Result<(), UserServiceError> {
+        self.user_repository.delete_by_id(user_id).await?;
+        self.publish_user_event(UserEvent::new(UserEventType::UserDeleted, *user_id));
+        log::info!("User {} deleted", user_id);
         Ok(())
     }
 
+    fn publish_user_event(&self, user_event: UserEvent) {
+        // user data is already changed, event publishing fail should not fail the request
+        if let Err(err) = self.user_repository.publish_user_event(&user_event) {
+            log::error!("Unable to publish {:?}: {}", user_event, err);
+        }
+    }
+
     pub async fn find_user_by_credentials(
         &self,
         credentials: &Credentials,
@@ -226,3 +989,14 @@ impl UserService {
         Ok(result)
     }
 }
+
+fn attach_profile_to_user(user_profile: UserProfile, user_id: &ObjectId) -> UserProfile {
+    match user_profile {
+        // CyberSherlock profile keeps system user id
+        UserProfile::CyberSherlock(mut cyber_sherlock_profile) => {
+            cyber_sherlock_profile.user_id = user_id.to_owned();
+            UserProfile::CyberSherlock(cyber_sherlock_profile)
+        }
+        profile => profile,
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use bson::oid::ObjectId;
+    use chrono::{Duration, Utc};
+
+    use crate::{
+        app::{
+            app_data::AppData,
+            models::{
+                common::AuthProviders,
+                session_metadata::SessionMetadata,
+                user_audit::{UserAuditEvent, UserAuditRecord},
+                user_profile::UserProfile,
+                user_status::{Suspension, UserStatus},
+            },
+            repositories::user::repository::UserRepository,
+            services::{
+                cache::{common::CacheServiceType, service::RedisCacheService},
+                storage::service::StorageService,
+                user::error::{UserServiceError, UserStatusError},
+            },
+        },
+        config::user_config::UserConfig,
+        tests::test_data::TestData,
+    };
+
+    #[actix_rt::test]
+    async fn suspended_user_oauth_login_keeps_deletion() {
+        let app_data = AppData::new().await.expect("Error to create AppData");
+        let mut user_repository = get_user_repository().await;
+        let test_data = TestData::new();
+
+        let mut google_profile = test_data
+            .user
+            .google
+            .clone()
+            .expect("Bad test user google profile!");
+        // uniq email, the user must not be merged with other test users by verified email
+        google_profile.email = format!("fake_suspended_{}@gmail.com", ObjectId::new());
+
+        let mut user_service = app_data.user_service.lock().unwrap();
+        let user = user_service
+            .create_user_with_profile(UserProfile::Google(google_profile.clone()))
+            .await
+            .expect("Error to create test user");
+        // the deletion is scheduled on the repository to not depend on the grace period config
+        let result = user_repository
+            .schedule_user_deletion(
+                &user.id,
+                Utc::now() + Duration::days(1),
+                UserAuditRecord::new(
+                    UserAuditEvent::AccountDeletionScheduled,
+                    AuthProviders::Google,
+                    None,
+                ),
+            )
+            .await;
+        assert_eq!(result.is_ok(), true);
+        let result = user_service
+            .suspend_user(
+                &user.id,
+                Suspension::new("spam".to_string(), None, ObjectId::new()),
+            )
+            .await;
+        assert_eq!(result.is_ok(), true);
+
+        // Google callback with completed tokens
+        match user_service
+            .get_user_session(
+                test_data.google_session.tokens.clone(),
+                UserProfile::Google(google_profile),
+                SessionMetadata::new(),
+            )
+            .await
+        {
+            Err(UserServiceError::UserStatusError(UserStatusError::Suspended { .. })) => {}
+            Err(err) => assert!(false, "Unexpected error {}", err),
+            Ok(_) => assert!(false, "Suspended user logged in!?"),
+        };
+
+        // the rejected login doesn't cancel the deletion
+        match user_service.get_user_by_id(&user.id).await {
+            Ok(Some(user)) => {
+                assert_eq!(user.is_deletion_scheduled(), true);
+                assert_eq!(user.get_status(), UserStatus::PendingDeletion);
+                assert_eq!(user.is_suspended(), true);
+            }
+            Ok(None) => assert!(false, "User not found!?"),
+            Err(err) => assert!(false, "Error get_user_by_id {}", err),
+        };
+        drop(user_service);
+
+        let result = user_repository.delete_by_id(&user.id).await;
+        assert_eq!(result.is_ok(), true)
+    }
+
+    #[actix_rt::test]
+    async fn other_provider_account_with_same_email_is_not_merged() {
+        let app_data = AppData::new().await.expect("Error to create AppData");
+        let mut user_repository = get_user_repository().await;
+
+        let mut google_profile = TestData::new()
+            .user
+            .google
+            .expect("Bad test user google profile!");
+        google_profile.email = format!("fake_same_email_{}@gmail.com", ObjectId::new());
+        // other Google account with the same email, e.g. email alias
+        let mut other_google_profile = google_profile.clone();
+        other_google_profile.user_id = format!("fake_google_user_id_{}", ObjectId::new());
+
+        let mut user_service = app_data.user_service.lock().unwrap();
+        let user = user_service
+            .create_user_with_profile(UserProfile::Google(google_profile))
+            .await
+            .expect("Error to create test user");
+        // no merge confirmation, it can't be confirmed as the user has Google profile linked
+        let other_user = match user_service
+            .create_user_with_profile(UserProfile::Google(other_google_profile))
+            .await
+        {
+            Ok(other_user) => other_user,
+            Err(err) => return assert!(false, "Error create_user_with_profile {}", err),
+        };
+        assert_ne!(other_user.id, user.id);
+        drop(user_service);
+
+        for user_id in [user.id, other_user.id] {
+            let result = user_repository.delete_by_id(&user_id).await;
+            assert_eq!(result.is_ok(), true)
+        }
+    }
+
+    async fn get_user_repository() -> UserRepository {
+        let storage_service = StorageService::new()
+            .await
+            .expect("Error to create Storage");
+        let user_cache_service = RedisCacheService::new(CacheServiceType::User)
+            .expect("Error to create User Cache Service");
+        UserRepository::new(user_cache_service, UserConfig::new(), storage_service)
+    }
+}
@@ -0,0 +1,134 @@
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::{http::header, HttpRequest};
+use ipnet::IpNet;
+use maxminddb::{geoip2, Reader};
+
+use crate::{
+    app::models::session_metadata::{Location, SessionMetadata},
+    config::session_config::SessionConfig,
+};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const GEOIP_LOCALE: &str = "en";
+
+/// resolves session metadata of the client behind the trusted proxies
+pub struct ClientInfoResolver {
+    trusted_proxies: Vec<IpNet>,
+    geoip_reader: Option<Reader<Vec<u8>>>,
+}
+
+impl ClientInfoResolver {
+    pub fn new(config: &SessionConfig) -> Self {
+        let geoip_reader = config.geoip_database_path.as_ref().map(|path| {
+            Reader::open_readfile(path)
+                .unwrap_or_else(|err| panic!("Unable to open GeoIP database {}: {}", path, err))
+        });
+        ClientInfoResolver {
+            trusted_proxies: config.trusted_proxies.clone(),
+            geoip_reader,
+        }
+    }
+
+    pub fn get_session_metadata(&self, request: &HttpRequest) -> SessionMetadata {
+        let mut session_metadata = SessionMetadata::new();
+        session_metadata.set_metadata_from_request(request);
+        if let Some(client_ip) = self.get_client_ip(request) {
+            session_metadata.set_client_ip(client_ip.to_string(), self.get_location(client_ip));
+        }
+        session_metadata
+    }
+
+    /// forwarded headers are taken into account only if the peer is a trusted proxy,
+    /// the client is the first not trusted address from the right of the proxies chain
+    pub fn get_client_ip(&self, request: &HttpRequest) -> Option<IpAddr> {
+        let peer_ip = request.peer_addr()?.ip();
+        if !self.is_trusted_proxy(&peer_ip) {
+            return Some(peer_ip);
+        }
+        let forwarded_ips = ClientInfoResolver::get_forwarded_ips(request);
+        match forwarded_ips
+            .iter()
+            .rev()
+            .find(|ip| !self.is_trusted_proxy(ip))
+        {
+            Some(client_ip) => Some(*client_ip),
+            // all the chain is trusted, the first address is the client
+            None => forwarded_ips.first().copied().or(Some(peer_ip)),
+        }
+    }
+
+    pub fn get_location(&self, ip: IpAddr) -> Option<Location> {
+        let reader = self.geoip_reader.as_ref()?;
+        let city = match reader.lookup::<geoip2::City>(ip) {
+            Ok(city) => city,
+            Err(err) => {
+                log::debug!("Unable to get GeoIP location of {}: {}", ip, err);
+                return None;
+            }
+        };
+        Some(Location {
+            country: city
+                .country
+                .and_then(|country| country.iso_code)
+                .map(|iso_code| iso_code.to_string()),
+            city: city
+                .city
+                .and_then(|city| city.names)
+                .and_then(|names| names.get(GEOIP_LOCALE).map(|name| name.to_string())),
+        })
+    }
+
+    fn is_trusted_proxy(&self, ip: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|proxy| proxy.contains(ip))
+    }
+
+    /// returns proxies chain from Forwarded(RFC 7239) or X-Forwarded-For header
+    fn get_forwarded_ips(request: &HttpRequest) -> Vec<IpAddr> {
+        if let Some(forwarded) = request
+            .headers()
+            .get(header::FORWARDED)
+            .and_then(|value| value.to_str().ok())
+        {
+            return forwarded
+                .split(',')
+                .filter_map(|element| {
+                    element.split(';').find_map(|pair| {
+                        let (name, value) = pair.trim().split_once('=')?;
+                        if name.eq_ignore_ascii_case("for") {
+                            ClientInfoResolver::parse_node(value)
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .collect();
+        }
+        request
+            .headers()
+            .get(X_FORWARDED_FOR)
+            .and_then(|value| value.to_str().ok())
+            .map(|x_forwarded_for| {
+                x_forwarded_for
+                    .split(',')
+                    .filter_map(ClientInfoResolver::parse_node)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// parses "192.0.2.43", "192.0.2.43:47011", "\"[2001:db8::17]:4711\"" node values,
+    /// obfuscated and "unknown" nodes are skipped
+    fn parse_node(node: &str) -> Option<IpAddr> {
+        let node = node.trim().trim_matches('"');
+        if let Ok(ip) = node.parse::<IpAddr>() {
+            return Some(ip);
+        }
+        if let Ok(socket_addr) = node.parse::<SocketAddr>() {
+            return Some(socket_addr.ip());
+        }
+        node.strip_prefix('[')
+            .and_then(|node| node.strip_suffix(']'))
+            .and_then(|ip| ip.parse::<IpAddr>().ok())
+    }
+}
@@ -25,6 +25,23 @@ where
     Ok(token_data.claims)
 }
 
+/// decodes the token signed with the secret by `encode_claims`, expiration is always checked
+pub fn decode_token_with_secret<T>(token: &str, secret: &str, audience: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_audience(&[audience]);
+
+    let token_data: TokenData<T> = decode(
+        token,
+        &DecodingKey::from_secret(secret.as_ref()),
+        &validation,
+    )?;
+
+    Ok(token_data.claims)
+}
+
 pub fn decoding_key_from_cert(modulus: &str, exponent: &str) -> Result<DecodingKey, Error> {
     let key = DecodingKey::from_rsa_components(modulus, exponent)?;
     Ok(key)
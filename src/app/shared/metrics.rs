@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// process counter exposed on the metrics endpoint in Prometheus text format
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    value: AtomicU64,
+}
+
+impl Counter {
+    const fn new(name: &'static str, help: &'static str) -> Self {
+        Counter {
+            name,
+            help,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    pub fn inc_by(&self, value: u64) {
+        self.value.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+pub static SESSION_INDEXES_SCANNED: Counter = Counter::new(
+    "session_indexes_scanned_total",
+    "User sessions indexes scanned by the sweeper",
+);
+pub static SESSION_INDEX_SESSIONS_REMOVED: Counter = Counter::new(
+    "session_index_sessions_removed_total",
+    "Expired sessions removed from user sessions indexes on read and by the sweeper",
+);
+pub static SESSION_INDEXES_REMOVED: Counter = Counter::new(
+    "session_indexes_removed_total",
+    "User sessions indexes removed as all their sessions are expired",
+);
+
+static COUNTERS: [&Counter; 3] = [
+    &SESSION_INDEXES_SCANNED,
+    &SESSION_INDEX_SESSIONS_REMOVED,
+    &SESSION_INDEXES_REMOVED,
+];
+
+/// renders all the counters in Prometheus text exposition format
+pub fn render_metrics() -> String {
+    COUNTERS
+        .iter()
+        .map(|counter| {
+            format!(
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}\n",
+                name = counter.name,
+                help = counter.help,
+                value = counter.get()
+            )
+        })
+        .collect()
+}
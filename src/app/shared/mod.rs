@@ -1 +1,5 @@
+pub mod client_info;
 pub mod jwt;
+pub mod metrics;
+pub mod normalization;
+mod test;
@@ -0,0 +1,36 @@
+use std::str::FromStr;
+
+use phonenumber::{country, Mode};
+
+/// returns canonical email to search users by: lowercased local part
+/// and ASCII(punycode) lowercased domain, None in case email is invalid
+pub fn normalize_email(email: &str) -> Option<String> {
+    let (local_part, domain) = email.trim().rsplit_once('@')?;
+    if local_part.is_empty() || domain.is_empty() {
+        return None;
+    }
+    let ascii_domain = match idna::domain_to_ascii(domain) {
+        Ok(d) => d,
+        Err(err) => {
+            log::debug!("Unable to normalize email domain {}: {:?}", domain, err);
+            return None;
+        }
+    };
+    Some(format!("{}@{}", local_part.to_lowercase(), ascii_domain))
+}
+
+/// returns phone in E.164 format, phone without country code is parsed
+/// with default region(ISO 3166-1 alpha-2 code), None in case phone is invalid
+pub fn normalize_phone(phone: &str, default_region: &str) -> Option<String> {
+    let region = country::Id::from_str(default_region).ok();
+    match phonenumber::parse(region, phone.trim()) {
+        Ok(phone_number) if phonenumber::is_valid(&phone_number) => {
+            Some(phone_number.format().mode(Mode::E164).to_string())
+        }
+        Ok(_) => None,
+        Err(err) => {
+            log::debug!("Unable to normalize phone {}: {:?}", phone, err);
+            None
+        }
+    }
+}
@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, SocketAddr};
+
+    use actix_web::{http::header, test::TestRequest};
+
+    use crate::{
+        app::shared::client_info::ClientInfoResolver, config::session_config::SessionConfig,
+    };
+
+    const PEER_ADDR: &str = "10.0.0.2:40000";
+    const CLIENT_IP: &str = "203.0.113.7";
+
+    #[test]
+    fn client_ip_of_untrusted_peer() {
+        let resolver = get_resolver(&[]);
+
+        // forwarded headers of not trusted peer are ignored
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((header::X_FORWARDED_FOR, CLIENT_IP))
+            .insert_header((header::FORWARDED, format!("for={}", CLIENT_IP)))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some("10.0.0.2".parse::<IpAddr>().unwrap())
+        );
+    }
+
+    #[test]
+    fn client_ip_behind_trusted_proxies() {
+        let resolver = get_resolver(&["10.0.0.0/8"]);
+
+        // the client is the first not trusted address from the right
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((
+                header::X_FORWARDED_FOR,
+                format!("198.51.100.1, {}, 10.0.0.1", CLIENT_IP),
+            ))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some(CLIENT_IP.parse::<IpAddr>().unwrap())
+        );
+
+        // Forwarded header takes precedence over X-Forwarded-For
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((header::X_FORWARDED_FOR, "198.51.100.1"))
+            .insert_header((
+                header::FORWARDED,
+                format!("for={};proto=https, for=10.0.0.1", CLIENT_IP),
+            ))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some(CLIENT_IP.parse::<IpAddr>().unwrap())
+        );
+    }
+
+    #[test]
+    fn client_ip_of_fully_trusted_chain() {
+        let resolver = get_resolver(&["10.0.0.0/8"]);
+
+        // the first address of the chain is the client
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((header::X_FORWARDED_FOR, "10.0.0.5, 10.0.0.1"))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some("10.0.0.5".parse::<IpAddr>().unwrap())
+        );
+
+        // no forwarded headers, the peer is the client
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some("10.0.0.2".parse::<IpAddr>().unwrap())
+        );
+    }
+
+    #[test]
+    fn client_ip_from_forwarded_nodes() {
+        let resolver = get_resolver(&["10.0.0.0/8"]);
+
+        // IPv6 node is quoted and bracketed, it can have a port
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((header::FORWARDED, "for=\"[2001:db8::17]:4711\""))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some("2001:db8::17".parse::<IpAddr>().unwrap())
+        );
+
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((header::FORWARDED, "for=\"[2001:db8::17]\""))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some("2001:db8::17".parse::<IpAddr>().unwrap())
+        );
+
+        // IPv4 node with port
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((header::X_FORWARDED_FOR, format!("{}:47011", CLIENT_IP)))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some(CLIENT_IP.parse::<IpAddr>().unwrap())
+        );
+
+        // unknown and obfuscated nodes are skipped
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((
+                header::FORWARDED,
+                format!("for={}, for=unknown, for=_hidden, for=10.0.0.1", CLIENT_IP),
+            ))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some(CLIENT_IP.parse::<IpAddr>().unwrap())
+        );
+
+        // only not parsable nodes, the peer is the client
+        let request = TestRequest::default()
+            .peer_addr(PEER_ADDR.parse::<SocketAddr>().unwrap())
+            .insert_header((header::FORWARDED, "for=unknown"))
+            .to_http_request();
+        assert_eq!(
+            resolver.get_client_ip(&request),
+            Some("10.0.0.2".parse::<IpAddr>().unwrap())
+        );
+    }
+
+    fn get_resolver(trusted_proxies: &[&str]) -> ClientInfoResolver {
+        let mut session_config = SessionConfig::new();
+        session_config.trusted_proxies = trusted_proxies
+            .iter()
+            .map(|proxy| proxy.parse().expect("Bad test trusted proxy"))
+            .collect();
+        session_config.geoip_database_path = None;
+        ClientInfoResolver::new(&session_config)
+    }
+}
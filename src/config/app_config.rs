@@ -7,6 +7,8 @@ pub struct AppConfig {
     pub app_id: String,
     pub auth_callback_url: String,
     pub cache_state_ttl_sec: u64,
+    pub contact_change_ttl_sec: u64,
+    pub password_reset_ttl_sec: u64,
     pub server_address: String,
     pub server_port: u16,
     pub jwt_access_token_ttl_sec: i64,
@@ -59,10 +61,24 @@ impl AppConfig {
             .parse()
             .expect("Invalid CACHE_STATE_TTL_SEC");
 
+        // pending email/phone change waits for the code confirmation during the ttl
+        let contact_change_ttl_sec: u64 = dotenv::var("CONTACT_CHANGE_TTL_SEC")
+            .expect("CONTACT_CHANGE_TTL_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid CONTACT_CHANGE_TTL_SEC");
+
+        // password reset token is valid once during the ttl
+        let password_reset_ttl_sec: u64 = dotenv::var("PASSWORD_RESET_TTL_SEC")
+            .expect("PASSWORD_RESET_TTL_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid PASSWORD_RESET_TTL_SEC");
+
         Self {
             app_id,
             auth_callback_url,
             cache_state_ttl_sec,
+            contact_change_ttl_sec,
+            password_reset_ttl_sec,
             jwt_access_token_ttl_sec,
             jwt_refresh_token_ttl_sec,
             jwt_secret,
@@ -1,13 +1,93 @@
+use std::{collections::HashMap, str::FromStr};
+
 use actix_web::cookie::{Key, SameSite};
 use cs_shared_lib::validation::validate_integer_in_range;
 use dotenv::dotenv;
+use ipnet::IpNet;
+
+use crate::app::models::role::Role;
 
 use super::cookie_config::{CookieConfiguration, CookieContentSecurity};
 
+/// what to do with a new session when the user has max number of active sessions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionLimitPolicy {
+    Reject,
+    EvictOldest,
+    EvictLeastRecentlyUsed,
+}
+
+impl FromStr for SessionLimitPolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "reject" => Ok(SessionLimitPolicy::Reject),
+            "evict_oldest" => Ok(SessionLimitPolicy::EvictOldest),
+            "evict_lru" => Ok(SessionLimitPolicy::EvictLeastRecentlyUsed),
+            _ => Err("Invalid SessionLimitPolicy"),
+        }
+    }
+}
+
+/// where the session middleware takes the session from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionAuthMode {
+    // private session cookie
+    Cookie,
+    // opaque session token in `Authorization: Bearer` header
+    BearerSession,
+    // CyberSherlock access JWT in `Authorization: Bearer` header
+    BearerJwt,
+}
+
+impl FromStr for SessionAuthMode {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "cookie" => Ok(SessionAuthMode::Cookie),
+            "bearer_session" => Ok(SessionAuthMode::BearerSession),
+            "bearer_jwt" => Ok(SessionAuthMode::BearerJwt),
+            _ => Err("Invalid SessionAuthMode"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SessionLimit {
+    pub max_sessions: usize,
+    pub policy: SessionLimitPolicy,
+}
+
 #[derive(Clone, Debug)]
 pub struct SessionConfig {
     pub cookie_config: CookieConfiguration,
+    // absolute session lifetime since login
     pub session_ttl_sec: u64,
+    // session expires without user activity during the timeout
+    pub session_idle_timeout_sec: u64,
+    // min interval between session activity updates to not write the session on every request
+    pub session_activity_update_interval_sec: u64,
+    // max active sessions per user, 0 means no limit, user can have own limit
+    pub max_sessions: u64,
+    // max active sessions of the user role(by role name) overrides the default limit
+    pub role_max_sessions: HashMap<String, u64>,
+    pub session_limit_policy: SessionLimitPolicy,
+    // proxies allowed to set client ip in X-Forwarded-For/Forwarded headers
+    pub trusted_proxies: Vec<IpNet>,
+    // local GeoIP2/GeoLite2 City database file to resolve session location
+    pub geoip_database_path: Option<String>,
+    // interval to remove expired sessions from user sessions sets
+    pub index_cleanup_interval_sec: u64,
+    // anonymous session lifetime, it's stored only when a handler requires it
+    pub anonymous_session_ttl_sec: u64,
+    // max anonymous sessions stored from one client ip during anonymous session ttl, 0 means no limit
+    pub anonymous_sessions_per_ip: u64,
+    // auth modes accepted by default
+    pub auth_modes: Vec<SessionAuthMode>,
+    // auth modes accepted on the api scope instead of the default ones
+    pub scope_auth_modes: HashMap<String, Vec<SessionAuthMode>>,
 }
 
 impl SessionConfig {
@@ -24,6 +104,134 @@ impl SessionConfig {
             panic!("Session ttl out of the range(1 min - 30 days)");
         }
 
+        let session_idle_timeout_sec = dotenv::var("SESSION_IDLE_TIMEOUT_SEC")
+            .expect("SESSION_IDLE_TIMEOUT_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid SESSION_IDLE_TIMEOUT_SEC");
+
+        // Session idle timeout in range (1 min - session ttl)
+        if !validate_integer_in_range(session_idle_timeout_sec, 60, session_ttl_sec) {
+            panic!("Session idle timeout out of the range(1 min - session ttl)");
+        }
+
+        let session_activity_update_interval_sec =
+            dotenv::var("SESSION_ACTIVITY_UPDATE_INTERVAL_SEC")
+                .expect("SESSION_ACTIVITY_UPDATE_INTERVAL_SEC environment variable is not set")
+                .parse()
+                .expect("Invalid SESSION_ACTIVITY_UPDATE_INTERVAL_SEC");
+
+        if session_activity_update_interval_sec >= session_idle_timeout_sec {
+            panic!("Session activity update interval has to be less than session idle timeout");
+        }
+
+        let max_sessions = dotenv::var("SESSION_MAX_SESSIONS")
+            .expect("SESSION_MAX_SESSIONS environment variable is not set")
+            .parse()
+            .expect("Invalid SESSION_MAX_SESSIONS");
+
+        // Max sessions in range (0(unlimited) - 1000)
+        if !validate_integer_in_range(max_sessions, 0, 1000) {
+            panic!("Max sessions out of the range(0 - 1000)");
+        }
+
+        // semicolon separated roles with their limits, e.g. "admin=2;support=5",
+        // a role typo would silently leave the role with the default limit, so it panics
+        let role_max_sessions = dotenv::var("SESSION_ROLE_MAX_SESSIONS")
+            .unwrap_or_default()
+            .split(';')
+            .map(|role| role.trim())
+            .filter(|role| !role.is_empty())
+            .map(|role| {
+                let (role, max_sessions) = role
+                    .split_once('=')
+                    .expect("Invalid SESSION_ROLE_MAX_SESSIONS");
+                let max_sessions: u64 = max_sessions
+                    .trim()
+                    .parse()
+                    .expect("Invalid SESSION_ROLE_MAX_SESSIONS");
+                if !validate_integer_in_range(max_sessions, 0, 1000) {
+                    panic!("Role max sessions out of the range(0 - 1000)");
+                }
+                let role = Role::from_str(role).unwrap_or_else(|err| {
+                    panic!("Invalid SESSION_ROLE_MAX_SESSIONS role {}: {}", role, err)
+                });
+                (role.to_string(), max_sessions)
+            })
+            .collect();
+
+        let session_limit_policy = dotenv::var("SESSION_LIMIT_POLICY")
+            .expect("SESSION_LIMIT_POLICY environment variable is not set")
+            .parse()
+            .expect("Invalid SESSION_LIMIT_POLICY");
+
+        // comma separated ip addresses or networks, empty means no proxy is trusted
+        let trusted_proxies = dotenv::var("SESSION_TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|proxy| proxy.trim())
+            .filter(|proxy| !proxy.is_empty())
+            .map(|proxy| {
+                proxy
+                    .parse::<IpNet>()
+                    .or_else(|_| proxy.parse::<std::net::IpAddr>().map(IpNet::from))
+                    .expect("Invalid SESSION_TRUSTED_PROXIES")
+            })
+            .collect();
+
+        let geoip_database_path = dotenv::var("SESSION_GEOIP_DATABASE_PATH")
+            .ok()
+            .filter(|path| !path.is_empty());
+
+        let anonymous_session_ttl_sec = dotenv::var("SESSION_ANONYMOUS_TTL_SEC")
+            .expect("SESSION_ANONYMOUS_TTL_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid SESSION_ANONYMOUS_TTL_SEC");
+
+        // Anonymous session ttl in range (1 min - session idle timeout)
+        if !validate_integer_in_range(anonymous_session_ttl_sec, 60, session_idle_timeout_sec) {
+            panic!("Anonymous session ttl out of the range(1 min - session idle timeout)");
+        }
+
+        let anonymous_sessions_per_ip = dotenv::var("SESSION_ANONYMOUS_MAX_PER_IP")
+            .expect("SESSION_ANONYMOUS_MAX_PER_IP environment variable is not set")
+            .parse()
+            .expect("Invalid SESSION_ANONYMOUS_MAX_PER_IP");
+
+        let index_cleanup_interval_sec = dotenv::var("SESSION_INDEX_CLEANUP_INTERVAL_SEC")
+            .expect("SESSION_INDEX_CLEANUP_INTERVAL_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid SESSION_INDEX_CLEANUP_INTERVAL_SEC");
+
+        if index_cleanup_interval_sec == 0 {
+            panic!("Session index cleanup interval has to be greater than 0");
+        }
+
+        let auth_modes = SessionConfig::parse_auth_modes(
+            &dotenv::var("SESSION_AUTH_MODES")
+                .expect("SESSION_AUTH_MODES environment variable is not set"),
+        )
+        .expect("Invalid SESSION_AUTH_MODES");
+
+        if auth_modes.is_empty() {
+            panic!("At least one session auth mode has to be accepted");
+        }
+
+        // semicolon separated scopes with their auth modes, e.g. "/admin=cookie;/user=cookie,bearer_jwt"
+        let scope_auth_modes = dotenv::var("SESSION_SCOPE_AUTH_MODES")
+            .unwrap_or_default()
+            .split(';')
+            .map(|scope| scope.trim())
+            .filter(|scope| !scope.is_empty())
+            .map(|scope| {
+                let (scope, modes) = scope
+                    .split_once('=')
+                    .expect("Invalid SESSION_SCOPE_AUTH_MODES");
+                let modes = SessionConfig::parse_auth_modes(modes)
+                    .expect("Invalid SESSION_SCOPE_AUTH_MODES");
+                (scope.trim().to_string(), modes)
+            })
+            .collect();
+
         let cookie_key = dotenv::var("SESSION_COOKIE_KEY")
             .expect("Missing the SESSION_COOKIE_KEY environment variable.");
 
@@ -40,6 +248,65 @@ impl SessionConfig {
                 key: Key::from(cookie_key.as_bytes()),
             },
             session_ttl_sec,
+            session_idle_timeout_sec,
+            session_activity_update_interval_sec,
+            max_sessions,
+            role_max_sessions,
+            session_limit_policy,
+            trusted_proxies,
+            geoip_database_path,
+            index_cleanup_interval_sec,
+            anonymous_session_ttl_sec,
+            anonymous_sessions_per_ip,
+            auth_modes,
+            scope_auth_modes,
+        }
+    }
+
+    /// api scope own auth modes override the default ones
+    pub fn get_auth_modes(&self, scope: &str) -> Vec<SessionAuthMode> {
+        self.scope_auth_modes
+            .get(scope)
+            .unwrap_or(&self.auth_modes)
+            .clone()
+    }
+
+    /// parses comma separated auth modes
+    fn parse_auth_modes(modes: &str) -> Result<Vec<SessionAuthMode>, &'static str> {
+        modes
+            .split(',')
+            .filter(|mode| !mode.trim().is_empty())
+            .map(SessionAuthMode::from_str)
+            .collect()
+    }
+
+    /// user own limit overrides the role one, the role one overrides the default one,
+    /// the strictest limit of the user roles is taken, None means no limit
+    pub fn get_session_limit(
+        &self,
+        user_max_sessions: Option<u64>,
+        user_roles: &[String],
+    ) -> Option<SessionLimit> {
+        let role_max_sessions = user_roles
+            .iter()
+            .filter_map(|role| self.role_max_sessions.get(role))
+            // 0 means no limit, it's the least strict one
+            .min_by_key(|max_sessions| match max_sessions {
+                0 => u64::MAX,
+                max_sessions => **max_sessions,
+            })
+            .copied();
+        match user_max_sessions
+            .or(role_max_sessions)
+            .unwrap_or(self.max_sessions)
+        {
+            0 => None,
+            max_sessions => Some(SessionLimit {
+                max_sessions: max_sessions as usize,
+                policy: self.session_limit_policy,
+            }),
         }
     }
 }
+
+mod test;
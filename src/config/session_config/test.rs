@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::config::session_config::{SessionAuthMode, SessionConfig};
+
+    #[test]
+    fn parse_auth_modes() {
+        assert_eq!(
+            SessionConfig::parse_auth_modes("cookie"),
+            Ok(vec![SessionAuthMode::Cookie])
+        );
+        // modes are trimmed and case insensitive, empty ones are skipped
+        assert_eq!(
+            SessionConfig::parse_auth_modes(" Cookie, bearer_session,,BEARER_JWT "),
+            Ok(vec![
+                SessionAuthMode::Cookie,
+                SessionAuthMode::BearerSession,
+                SessionAuthMode::BearerJwt
+            ])
+        );
+        assert_eq!(SessionConfig::parse_auth_modes(""), Ok(vec![]));
+        assert_eq!(
+            SessionConfig::parse_auth_modes("cookie,bearer").is_err(),
+            true
+        );
+    }
+
+    #[test]
+    fn get_auth_modes() {
+        let mut session_config = SessionConfig::new();
+        session_config.auth_modes = vec![SessionAuthMode::Cookie];
+        session_config.scope_auth_modes = HashMap::from([(
+            String::from("/user"),
+            vec![SessionAuthMode::Cookie, SessionAuthMode::BearerJwt],
+        )]);
+
+        // scope own modes override the default ones
+        assert_eq!(
+            session_config.get_auth_modes("/user"),
+            vec![SessionAuthMode::Cookie, SessionAuthMode::BearerJwt]
+        );
+        assert_eq!(
+            session_config.get_auth_modes("/admin"),
+            vec![SessionAuthMode::Cookie]
+        );
+    }
+}
@@ -4,6 +4,14 @@ use serde::Deserialize;
 #[derive(Deserialize, Clone, Debug)]
 pub struct UserConfig {
     pub user_cache_ttl_sec: u64,
+    pub auto_merge_verified_email: bool,
+    pub merge_confirmation_ttl_sec: u64,
+    pub phone_default_region: String,
+    pub reauthentication_max_age_sec: i64,
+    pub deletion_grace_period_sec: i64,
+    pub deletion_purge_interval_sec: u64,
+    pub data_export_url: String,
+    pub data_export_link_ttl_sec: u64,
 }
 
 impl UserConfig {
@@ -15,6 +23,66 @@ impl UserConfig {
             .parse()
             .expect("Invalid USER_CACHE_TTL_SEC");
 
-        Self { user_cache_ttl_sec }
+        // Attach a new provider profile to existing user with the same verified email
+        let auto_merge_verified_email = dotenv::var("USER_AUTO_MERGE_VERIFIED_EMAIL")
+            .expect("USER_AUTO_MERGE_VERIFIED_EMAIL environment variable is not set")
+            .parse()
+            .expect("Invalid USER_AUTO_MERGE_VERIFIED_EMAIL");
+
+        let merge_confirmation_ttl_sec = dotenv::var("USER_MERGE_CONFIRMATION_TTL_SEC")
+            .expect("USER_MERGE_CONFIRMATION_TTL_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid USER_MERGE_CONFIRMATION_TTL_SEC");
+
+        // ISO 3166-1 alpha-2 region to parse phones without country code
+        let phone_default_region = dotenv::var("USER_PHONE_DEFAULT_REGION")
+            .expect("USER_PHONE_DEFAULT_REGION environment variable is not set")
+            .to_uppercase();
+
+        if phone_default_region.len() != 2 {
+            panic!("Invalid USER_PHONE_DEFAULT_REGION, it has to be 2 letters region code");
+        }
+
+        // sensitive operations(account deletion) require a recent login
+        let reauthentication_max_age_sec = dotenv::var("USER_REAUTHENTICATION_MAX_AGE_SEC")
+            .expect("USER_REAUTHENTICATION_MAX_AGE_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid USER_REAUTHENTICATION_MAX_AGE_SEC");
+
+        // deleted account is restorable during the period, 0 means immediate deletion
+        let deletion_grace_period_sec = dotenv::var("USER_DELETION_GRACE_PERIOD_SEC")
+            .expect("USER_DELETION_GRACE_PERIOD_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid USER_DELETION_GRACE_PERIOD_SEC");
+
+        let deletion_purge_interval_sec = dotenv::var("USER_DELETION_PURGE_INTERVAL_SEC")
+            .expect("USER_DELETION_PURGE_INTERVAL_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid USER_DELETION_PURGE_INTERVAL_SEC");
+
+        if deletion_purge_interval_sec == 0 {
+            panic!("Invalid USER_DELETION_PURGE_INTERVAL_SEC, it has to be greater than 0");
+        }
+
+        // public url of data export download endpoint, export token is appended to the url
+        let data_export_url = dotenv::var("USER_DATA_EXPORT_URL")
+            .expect("USER_DATA_EXPORT_URL environment variable is not set");
+
+        let data_export_link_ttl_sec = dotenv::var("USER_DATA_EXPORT_LINK_TTL_SEC")
+            .expect("USER_DATA_EXPORT_LINK_TTL_SEC environment variable is not set")
+            .parse()
+            .expect("Invalid USER_DATA_EXPORT_LINK_TTL_SEC");
+
+        Self {
+            user_cache_ttl_sec,
+            auto_merge_verified_email,
+            merge_confirmation_ttl_sec,
+            phone_default_region,
+            reauthentication_max_age_sec,
+            deletion_grace_period_sec,
+            deletion_purge_interval_sec,
+            data_export_url,
+            data_export_link_ttl_sec,
+        }
     }
 }
@@ -4,6 +4,16 @@ pub mod tests;
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
+    // Command line arguments run admin tools instead of the service
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(err) = app::cli::run_command(args).await {
+            // scripts running the command check the exit code
+            eprintln!("Error to run command: {:?}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     if let Err(err) = app::run().await {
         println!("Error to start Auth Service: {:?}", err)
     }
@@ -23,6 +23,7 @@ impl TestData {
             email: String::from("fake_google_user_email@gmail.com"),
             email_verified: true,
             picture: Some(String::from("http://localhost")),
+            normalized_email: None,
         };
 
         // We need to keep uniq Google user_id